@@ -15,12 +15,28 @@ fn compile_ntuple_writer() -> Result<()> {
         .header("src/ntuple.h")
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
         .allowlist_function("ntuple_create_reader")
+        .allowlist_function("ntuple_create_reader_from_buffer")
         .allowlist_function("ntuple_read_event")
         .allowlist_function("ntuple_num_events")
         .allowlist_function("ntuple_delete_reader")
         .allowlist_function("ntuple_create_writer")
+        .allowlist_function("ntuple_create_memory_writer")
+        .allowlist_function("ntuple_finish_writer")
+        .allowlist_function("ntuple_writer_buffer")
         .allowlist_function("ntuple_write_event")
         .allowlist_function("ntuple_delete_writer")
+        .allowlist_function("ntuple_last_error")
+        .allowlist_function("ntuple_reader_tree_name")
+        .allowlist_function("ntuple_reader_title")
+        .allowlist_function("ntuple_reader_is_legacy")
+        .allowlist_function("ntuple_reader_num_leaves")
+        .allowlist_function("ntuple_reader_leaf_info")
+        .allowlist_function("ntuple_reader_get_string")
+        .allowlist_function("ntuple_reader_get_double")
+        .allowlist_function("ntuple_reader_get_int")
+        .allowlist_function("ntuple_writer_set_string")
+        .allowlist_function("ntuple_writer_set_double")
+        .allowlist_function("ntuple_writer_set_int")
         .newtype_enum("NTupleReadStatus")
         .newtype_enum("NTupleCreateError")
         .newtype_enum("NTupleWriteResult")
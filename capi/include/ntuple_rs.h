@@ -0,0 +1,168 @@
+#ifndef NTUPLE_RS_H
+#define NTUPLE_RS_H
+
+/* Generated with cbindgen, do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of an API call
+ */
+typedef enum NTupleRsStatus {
+  NTUPLE_RS_OK = 0,
+  /**
+   * A required pointer argument was null
+   */
+  NTUPLE_RS_NULL_POINTER,
+  /**
+   * A string argument was not valid UTF-8
+   */
+  NTUPLE_RS_INVALID_STRING,
+  /**
+   * Failed to create the output file
+   */
+  NTUPLE_RS_CREATE_FAILED,
+  /**
+   * The event is malformed or failed validation
+   */
+  NTUPLE_RS_INVALID_EVENT,
+  /**
+   * Failed to write the event
+   */
+  NTUPLE_RS_WRITE_FAILED,
+  /**
+   * Failed to write the remaining data and close the file
+   */
+  NTUPLE_RS_FINISH_FAILED,
+  /**
+   * Failed to split a file
+   */
+  NTUPLE_RS_SPLIT_FAILED,
+  /**
+   * Internal error
+   */
+  NTUPLE_RS_PANIC,
+} NTupleRsStatus;
+
+/**
+ * Opaque handle for an ntuple writer
+ */
+typedef struct NTupleRsWriter NTupleRsWriter;
+
+/**
+ * Event record
+ *
+ * The arrays `px`, `py`, `pz`, `energy`, and `pdg_code` have length
+ * `nparticle`, `user_weights` has length `nuser_weights`. `part` is
+ * one of 'B', 'I', 'R', 'V', 'S'.
+ */
+typedef struct NTupleRsEvent {
+  int32_t id;
+  int32_t nparticle;
+  const float *px;
+  const float *py;
+  const float *pz;
+  const float *energy;
+  double alphas;
+  const int32_t *pdg_code;
+  double weight;
+  double weight2;
+  double me_weight;
+  double me_weight2;
+  double x1;
+  double x2;
+  double x1p;
+  double x2p;
+  int32_t id1;
+  int32_t id2;
+  double fac_scale;
+  double ren_scale;
+  int32_t nuser_weights;
+  const double *user_weights;
+  char part;
+  int16_t alphas_power;
+} NTupleRsEvent;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Description of the last error in the current thread
+ *
+ * The returned string is valid until the next API call in the same
+ * thread.
+ */
+const char *ntuple_rs_last_error(void);
+
+/**
+ * Create a writer for the file at `path`
+ *
+ * On success, `*writer` points to the new writer, which has to be
+ * released with [ntuple_rs_writer_finish].
+ *
+ * # Safety
+ *
+ * `path` and `title` must be nul-terminated strings, `writer` must
+ * be a valid pointer.
+ */
+NTupleRsStatus ntuple_rs_writer_create(const char *path,
+                                       const char *title,
+                                       NTupleRsWriter **writer);
+
+/**
+ * Enable or disable validation of events before writing
+ *
+ * With `strict` non-zero, events are checked for inconsistencies
+ * and rejected with `NTUPLE_RS_INVALID_EVENT`.
+ *
+ * # Safety
+ *
+ * `writer` must have been created with [ntuple_rs_writer_create].
+ */
+NTupleRsStatus ntuple_rs_writer_set_strict(NTupleRsWriter *writer, int32_t strict);
+
+/**
+ * Write an event
+ *
+ * # Safety
+ *
+ * `writer` must have been created with [ntuple_rs_writer_create].
+ * `event` must point to a valid event record.
+ */
+NTupleRsStatus ntuple_rs_writer_write(NTupleRsWriter *writer, const NTupleRsEvent *event);
+
+/**
+ * Write all remaining data, close the file, and release the writer
+ *
+ * The writer must not be used afterwards, even if an error is
+ * returned.
+ *
+ * # Safety
+ *
+ * `writer` must have been created with [ntuple_rs_writer_create]
+ * and not been finished before.
+ */
+NTupleRsStatus ntuple_rs_writer_finish(NTupleRsWriter *writer);
+
+/**
+ * Split the file at `input` into `nparts` files of similar size
+ *
+ * The parts are written to `<prefix>.<n>.root`. Events with the
+ * same id are kept in the same part.
+ *
+ * # Safety
+ *
+ * `input` and `prefix` must be nul-terminated strings.
+ */
+NTupleRsStatus ntuple_rs_split(const char *input, const char *prefix, size_t nparts);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NTUPLE_RS_H */
@@ -0,0 +1,413 @@
+//! C API for writing ntuple files
+//!
+//! All functions return an [NTupleRsStatus]. In case of an error, a
+//! description can be obtained with [ntuple_rs_last_error]. The
+//! header `include/ntuple_rs.h` is generated with cbindgen, see
+//! `cbindgen.toml`.
+#![allow(non_camel_case_types)]
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    path::Path,
+    ptr, slice,
+};
+
+use ntuple::{
+    event::Part,
+    split::{split, SplitOptions, SplitSize},
+    validate::ValidationOptions,
+    Event, Writer,
+};
+
+/// Result of an API call
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum NTupleRsStatus {
+    NTUPLE_RS_OK = 0,
+    /// A required pointer argument was null
+    NTUPLE_RS_NULL_POINTER,
+    /// A string argument was not valid UTF-8
+    NTUPLE_RS_INVALID_STRING,
+    /// Failed to create the output file
+    NTUPLE_RS_CREATE_FAILED,
+    /// The event is malformed or failed validation
+    NTUPLE_RS_INVALID_EVENT,
+    /// Failed to write the event
+    NTUPLE_RS_WRITE_FAILED,
+    /// Failed to write the remaining data and close the file
+    NTUPLE_RS_FINISH_FAILED,
+    /// Failed to split a file
+    NTUPLE_RS_SPLIT_FAILED,
+    /// Internal error
+    NTUPLE_RS_PANIC,
+}
+use NTupleRsStatus::*;
+
+/// Event record
+///
+/// The arrays `px`, `py`, `pz`, `energy`, and `pdg_code` have length
+/// `nparticle`, `user_weights` has length `nuser_weights`. `part` is
+/// one of 'B', 'I', 'R', 'V', 'S'.
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct NTupleRsEvent {
+    pub id: i32,
+    pub nparticle: i32,
+    pub px: *const f32,
+    pub py: *const f32,
+    pub pz: *const f32,
+    pub energy: *const f32,
+    pub alphas: f64,
+    pub pdg_code: *const i32,
+    pub weight: f64,
+    pub weight2: f64,
+    pub me_weight: f64,
+    pub me_weight2: f64,
+    pub x1: f64,
+    pub x2: f64,
+    pub x1p: f64,
+    pub x2p: f64,
+    pub id1: i32,
+    pub id2: i32,
+    pub fac_scale: f64,
+    pub ren_scale: f64,
+    pub nuser_weights: i32,
+    pub user_weights: *const f64,
+    pub part: c_char,
+    pub alphas_power: i16,
+}
+
+impl Default for NTupleRsEvent {
+    fn default() -> Self {
+        Self {
+            id: 0,
+            nparticle: 0,
+            px: ptr::null(),
+            py: ptr::null(),
+            pz: ptr::null(),
+            energy: ptr::null(),
+            alphas: 0.,
+            pdg_code: ptr::null(),
+            weight: 0.,
+            weight2: 0.,
+            me_weight: 0.,
+            me_weight2: 0.,
+            x1: 0.,
+            x2: 0.,
+            x1p: 0.,
+            x2p: 0.,
+            id1: 0,
+            id2: 0,
+            fac_scale: 0.,
+            ren_scale: 0.,
+            nuser_weights: 0,
+            user_weights: ptr::null(),
+            part: b'B' as c_char,
+            alphas_power: 0,
+        }
+    }
+}
+
+/// Opaque handle for an ntuple writer
+pub struct NTupleRsWriter(Writer);
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+fn set_last_error(msg: impl std::fmt::Display) {
+    // interior nul bytes would truncate the message
+    let msg = msg.to_string().replace('\0', " ");
+    let msg = CString::new(msg).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = msg);
+}
+
+fn fail(status: NTupleRsStatus, msg: impl std::fmt::Display) -> NTupleRsStatus {
+    set_last_error(msg);
+    status
+}
+
+// Run `f`, turning panics into an error code
+fn guard(f: impl FnOnce() -> NTupleRsStatus) -> NTupleRsStatus {
+    catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|_| fail(NTUPLE_RS_PANIC, "Internal error"))
+}
+
+unsafe fn to_str<'a>(s: *const c_char) -> Result<&'a str, NTupleRsStatus> {
+    if s.is_null() {
+        return Err(fail(NTUPLE_RS_NULL_POINTER, "Null string argument"));
+    }
+    unsafe { CStr::from_ptr(s) }
+        .to_str()
+        .map_err(|err| fail(NTUPLE_RS_INVALID_STRING, err))
+}
+
+unsafe fn to_vec<T: Copy>(
+    ptr: *const T,
+    len: i32,
+    name: &str,
+) -> Result<Vec<T>, NTupleRsStatus> {
+    let Ok(len) = usize::try_from(len) else {
+        return Err(fail(
+            NTUPLE_RS_INVALID_EVENT,
+            format!("Negative length {len} of `{name}`"),
+        ));
+    };
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    if ptr.is_null() {
+        return Err(fail(NTUPLE_RS_NULL_POINTER, format!("`{name}` is null")));
+    }
+    Ok(unsafe { slice::from_raw_parts(ptr, len) }.to_vec())
+}
+
+impl NTupleRsEvent {
+    unsafe fn to_event(self) -> Result<Event, NTupleRsStatus> {
+        let n = self.nparticle;
+        #[allow(clippy::unnecessary_cast)]
+        let part = Part::try_from(self.part as u8)
+            .map_err(|err| fail(NTUPLE_RS_INVALID_EVENT, err))?;
+        unsafe {
+            Ok(Event {
+                id: self.id,
+                nparticle: self.nparticle,
+                px: to_vec(self.px, n, "px")?,
+                py: to_vec(self.py, n, "py")?,
+                pz: to_vec(self.pz, n, "pz")?,
+                energy: to_vec(self.energy, n, "energy")?,
+                alphas: self.alphas,
+                pdg_code: to_vec(self.pdg_code, n, "pdg_code")?,
+                weight: self.weight,
+                weight2: self.weight2,
+                me_weight: self.me_weight,
+                me_weight2: self.me_weight2,
+                x1: self.x1,
+                x2: self.x2,
+                x1p: self.x1p,
+                x2p: self.x2p,
+                id1: self.id1,
+                id2: self.id2,
+                fac_scale: self.fac_scale,
+                ren_scale: self.ren_scale,
+                user_weights: to_vec(
+                    self.user_weights,
+                    self.nuser_weights,
+                    "user_weights",
+                )?,
+                part,
+                alphas_power: self.alphas_power,
+            })
+        }
+    }
+}
+
+/// Description of the last error in the current thread
+///
+/// The returned string is valid until the next API call in the same
+/// thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn ntuple_rs_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ptr())
+}
+
+/// Create a writer for the file at `path`
+///
+/// On success, `*writer` points to the new writer, which has to be
+/// released with [ntuple_rs_writer_finish].
+///
+/// # Safety
+///
+/// `path` and `title` must be nul-terminated strings, `writer` must
+/// be a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ntuple_rs_writer_create(
+    path: *const c_char,
+    title: *const c_char,
+    writer: *mut *mut NTupleRsWriter,
+) -> NTupleRsStatus {
+    guard(|| {
+        if writer.is_null() {
+            return fail(NTUPLE_RS_NULL_POINTER, "`writer` is null");
+        }
+        let (path, title) = match unsafe { (to_str(path), to_str(title)) } {
+            (Ok(path), Ok(title)) => (path, title),
+            (Err(err), _) | (_, Err(err)) => return err,
+        };
+        match Writer::new(path, title) {
+            Ok(w) => {
+                let w = Box::new(NTupleRsWriter(w));
+                unsafe { *writer = Box::into_raw(w) };
+                NTUPLE_RS_OK
+            }
+            Err(err) => fail(NTUPLE_RS_CREATE_FAILED, err),
+        }
+    })
+}
+
+/// Enable or disable validation of events before writing
+///
+/// With `strict` non-zero, events are checked for inconsistencies
+/// and rejected with `NTUPLE_RS_INVALID_EVENT`.
+///
+/// # Safety
+///
+/// `writer` must have been created with [ntuple_rs_writer_create].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ntuple_rs_writer_set_strict(
+    writer: *mut NTupleRsWriter,
+    strict: i32,
+) -> NTupleRsStatus {
+    guard(|| {
+        let Some(writer) = (unsafe { writer.as_mut() }) else {
+            return fail(NTUPLE_RS_NULL_POINTER, "`writer` is null");
+        };
+        let opts = (strict != 0).then(ValidationOptions::default);
+        writer.0.set_strict(opts);
+        NTUPLE_RS_OK
+    })
+}
+
+/// Write an event
+///
+/// # Safety
+///
+/// `writer` must have been created with [ntuple_rs_writer_create].
+/// `event` must point to a valid event record.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ntuple_rs_writer_write(
+    writer: *mut NTupleRsWriter,
+    event: *const NTupleRsEvent,
+) -> NTupleRsStatus {
+    guard(|| {
+        let Some(writer) = (unsafe { writer.as_mut() }) else {
+            return fail(NTUPLE_RS_NULL_POINTER, "`writer` is null");
+        };
+        let Some(event) = (unsafe { event.as_ref() }) else {
+            return fail(NTUPLE_RS_NULL_POINTER, "`event` is null");
+        };
+        let event = match unsafe { event.to_event() } {
+            Ok(event) => event,
+            Err(err) => return err,
+        };
+        match writer.0.write(&event) {
+            Ok(()) => NTUPLE_RS_OK,
+            Err(err @ ntuple::writer::WriteError::Invalid(_)) => {
+                fail(NTUPLE_RS_INVALID_EVENT, err)
+            }
+            Err(err) => fail(NTUPLE_RS_WRITE_FAILED, err),
+        }
+    })
+}
+
+/// Write all remaining data, close the file, and release the writer
+///
+/// The writer must not be used afterwards, even if an error is
+/// returned.
+///
+/// # Safety
+///
+/// `writer` must have been created with [ntuple_rs_writer_create]
+/// and not been finished before.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ntuple_rs_writer_finish(
+    writer: *mut NTupleRsWriter,
+) -> NTupleRsStatus {
+    guard(|| {
+        if writer.is_null() {
+            return fail(NTUPLE_RS_NULL_POINTER, "`writer` is null");
+        }
+        let writer = unsafe { Box::from_raw(writer) };
+        match writer.0.finish() {
+            Ok(()) => NTUPLE_RS_OK,
+            Err(err) => fail(NTUPLE_RS_FINISH_FAILED, err),
+        }
+    })
+}
+
+/// Split the file at `input` into `nparts` files of similar size
+///
+/// The parts are written to `<prefix>.<n>.root`. Events with the
+/// same id are kept in the same part.
+///
+/// # Safety
+///
+/// `input` and `prefix` must be nul-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ntuple_rs_split(
+    input: *const c_char,
+    prefix: *const c_char,
+    nparts: usize,
+) -> NTupleRsStatus {
+    guard(|| {
+        let (input, prefix) = match unsafe { (to_str(input), to_str(prefix)) } {
+            (Ok(input), Ok(prefix)) => (input, prefix),
+            (Err(err), _) | (_, Err(err)) => return err,
+        };
+        let opts = SplitOptions {
+            keep_groups: true,
+            ..SplitOptions::new(SplitSize::Parts(nparts))
+        };
+        match split(Path::new(input), prefix, &opts) {
+            Ok(_) => NTUPLE_RS_OK,
+            Err(err) => fail(NTUPLE_RS_SPLIT_FAILED, err),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> String {
+        let msg = unsafe { CStr::from_ptr(ntuple_rs_last_error()) };
+        msg.to_str().unwrap().to_owned()
+    }
+
+    #[test]
+    fn convert_event() {
+        let px = [1f32, 2.];
+        let ev = NTupleRsEvent {
+            nparticle: 2,
+            px: px.as_ptr(),
+            py: px.as_ptr(),
+            pz: px.as_ptr(),
+            energy: px.as_ptr(),
+            ..Default::default()
+        };
+        let res = unsafe { ev.to_event() };
+        assert_eq!(res.unwrap_err(), NTUPLE_RS_NULL_POINTER);
+        assert_eq!(last_error(), "`pdg_code` is null");
+
+        let kf = [21, 21];
+        let ev = NTupleRsEvent {
+            pdg_code: kf.as_ptr(),
+            part: b'X' as c_char,
+            ..ev
+        };
+        let res = unsafe { ev.to_event() };
+        assert_eq!(res.unwrap_err(), NTUPLE_RS_INVALID_EVENT);
+
+        let ev = NTupleRsEvent {
+            part: b'R' as c_char,
+            ..ev
+        };
+        let event = unsafe { ev.to_event() }.unwrap();
+        assert_eq!(event.px, px);
+        assert_eq!(event.pdg_code, kf);
+        assert_eq!(event.part, Part::R);
+    }
+
+    #[test]
+    fn null_pointers() {
+        let status = unsafe { ntuple_rs_writer_finish(ptr::null_mut()) };
+        assert_eq!(status, NTUPLE_RS_NULL_POINTER);
+        let mut writer = ptr::null_mut();
+        let status = unsafe {
+            ntuple_rs_writer_create(ptr::null(), c"".as_ptr(), &mut writer)
+        };
+        assert_eq!(status, NTUPLE_RS_NULL_POINTER);
+        assert!(writer.is_null());
+    }
+}
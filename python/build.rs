@@ -0,0 +1,11 @@
+use anyhow::Result;
+use get_root_flags::get_root_flags;
+
+fn main() -> Result<()> {
+    // link arguments from the ntuple build script are not passed on
+    // to dependent crates, so we have to add them here
+    for flag in get_root_flags("--libs")? {
+        println!("cargo:rustc-link-arg={flag}");
+    }
+    Ok(())
+}
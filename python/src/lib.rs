@@ -0,0 +1,410 @@
+//! Python bindings for the ntuple crate
+//!
+//! Events are exchanged as dictionaries with the same keys as the
+//! fields of [ntuple::Event]. Batches of events are dictionaries of
+//! NumPy arrays, see [Reader::read_batch].
+use ::ntuple::{event::Part, Event};
+use numpy::{PyArray1, PyReadonlyArray1};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyValueError},
+    prelude::*,
+    types::{PyBytes, PyDict},
+};
+
+create_exception!(ntuple, NTupleError, PyException);
+
+fn to_py_err<E: std::error::Error>(err: E) -> PyErr {
+    let mut msg = err.to_string();
+    let mut source = err.source();
+    while let Some(err) = source {
+        msg += &format!(": {err}");
+        source = err.source();
+    }
+    NTupleError::new_err(msg)
+}
+
+/// Reader for ntuple files
+#[pyclass(unsendable, module = "ntuple")]
+struct Reader(::ntuple::Reader);
+
+#[pymethods]
+impl Reader {
+    #[new]
+    fn new(path: std::path::PathBuf) -> PyResult<Self> {
+        let reader = ::ntuple::Reader::new(path).map_err(to_py_err)?;
+        Ok(Self(reader))
+    }
+
+    /// Read from the contents of a ROOT file
+    #[staticmethod]
+    fn from_bytes(bytes: &Bound<'_, PyBytes>) -> PyResult<Self> {
+        let reader = ::ntuple::Reader::from_bytes(bytes.as_bytes())
+            .map_err(to_py_err)?;
+        Ok(Self(reader))
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.remaining_entries()
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(
+        &mut self,
+        py: Python<'py>,
+    ) -> PyResult<Option<Bound<'py, PyDict>>> {
+        match self.0.next() {
+            Some(event) => {
+                let event = event.map_err(to_py_err)?;
+                Ok(Some(event_to_dict(py, &event)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Read up to `size` events as a dictionary of NumPy arrays
+    ///
+    /// Returns `None` if there are no events left.
+    fn read_batch<'py>(
+        &mut self,
+        py: Python<'py>,
+        size: usize,
+    ) -> PyResult<Option<Bound<'py, PyDict>>> {
+        let mut batch = Batch::default();
+        for event in self.0.by_ref().take(size) {
+            batch.push(event.map_err(to_py_err)?);
+        }
+        if batch.id.is_empty() {
+            return Ok(None);
+        }
+        batch.into_dict(py).map(Some)
+    }
+}
+
+/// Writer for ntuple files
+#[pyclass(unsendable, module = "ntuple")]
+struct Writer(Option<::ntuple::Writer>);
+
+#[pymethods]
+impl Writer {
+    #[new]
+    #[pyo3(signature = (path, title = ""))]
+    fn new(path: std::path::PathBuf, title: &str) -> PyResult<Self> {
+        let writer = ::ntuple::Writer::new(path, title).map_err(to_py_err)?;
+        Ok(Self(Some(writer)))
+    }
+
+    /// Write an event given as a dictionary
+    ///
+    /// Missing keys take their default values.
+    fn write(&mut self, event: &Bound<'_, PyDict>) -> PyResult<()> {
+        let event = dict_to_event(event)?;
+        self.writer()?.write(&event).map_err(to_py_err)
+    }
+
+    /// Write all events in a batch as returned by `Reader.read_batch`
+    fn write_batch(&mut self, batch: &Bound<'_, PyDict>) -> PyResult<()> {
+        let writer = self.writer()?;
+        for event in Batch::from_dict(batch)?.into_events()? {
+            writer.write(&event).map_err(to_py_err)?;
+        }
+        Ok(())
+    }
+
+    /// Write all remaining data and close the file
+    fn close(&mut self) -> PyResult<()> {
+        match self.0.take() {
+            Some(writer) => writer.finish().map_err(to_py_err),
+            None => Ok(()),
+        }
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<Bound<'_, PyAny>>,
+        _exc_value: Option<Bound<'_, PyAny>>,
+        _traceback: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<()> {
+        self.close()
+    }
+}
+
+impl Writer {
+    fn writer(&mut self) -> PyResult<&mut ::ntuple::Writer> {
+        self.0
+            .as_mut()
+            .ok_or_else(|| NTupleError::new_err("Writer is closed"))
+    }
+}
+
+fn event_to_dict<'py>(
+    py: Python<'py>,
+    event: &Event,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("id", event.id)?;
+    dict.set_item("nparticle", event.nparticle)?;
+    dict.set_item("px", &event.px)?;
+    dict.set_item("py", &event.py)?;
+    dict.set_item("pz", &event.pz)?;
+    dict.set_item("energy", &event.energy)?;
+    dict.set_item("alphas", event.alphas)?;
+    dict.set_item("pdg_code", &event.pdg_code)?;
+    dict.set_item("weight", event.weight)?;
+    dict.set_item("weight2", event.weight2)?;
+    dict.set_item("me_weight", event.me_weight)?;
+    dict.set_item("me_weight2", event.me_weight2)?;
+    dict.set_item("x1", event.x1)?;
+    dict.set_item("x2", event.x2)?;
+    dict.set_item("x1p", event.x1p)?;
+    dict.set_item("x2p", event.x2p)?;
+    dict.set_item("id1", event.id1)?;
+    dict.set_item("id2", event.id2)?;
+    dict.set_item("fac_scale", event.fac_scale)?;
+    dict.set_item("ren_scale", event.ren_scale)?;
+    dict.set_item("user_weights", &event.user_weights)?;
+    dict.set_item("part", char::from(event.part))?;
+    dict.set_item("alphas_power", event.alphas_power)?;
+    Ok(dict)
+}
+
+fn dict_to_event(dict: &Bound<'_, PyDict>) -> PyResult<Event> {
+    fn get<'py, T: FromPyObjectOwned<'py> + Default>(
+        dict: &Bound<'py, PyDict>,
+        key: &str,
+    ) -> PyResult<T> {
+        match dict.get_item(key)? {
+            Some(val) => val.extract().map_err(Into::into),
+            None => Ok(T::default()),
+        }
+    }
+
+    let px: Vec<f32> = get(dict, "px")?;
+    let nparticle = match dict.get_item("nparticle")? {
+        Some(n) => n.extract()?,
+        None => i32::try_from(px.len())
+            .map_err(|_| PyValueError::new_err("Too many particles"))?,
+    };
+    Ok(Event {
+        id: get(dict, "id")?,
+        nparticle,
+        px,
+        py: get(dict, "py")?,
+        pz: get(dict, "pz")?,
+        energy: get(dict, "energy")?,
+        alphas: get(dict, "alphas")?,
+        pdg_code: get(dict, "pdg_code")?,
+        weight: get(dict, "weight")?,
+        weight2: get(dict, "weight2")?,
+        me_weight: get(dict, "me_weight")?,
+        me_weight2: get(dict, "me_weight2")?,
+        x1: get(dict, "x1")?,
+        x2: get(dict, "x2")?,
+        x1p: get(dict, "x1p")?,
+        x2p: get(dict, "x2p")?,
+        id1: get(dict, "id1")?,
+        id2: get(dict, "id2")?,
+        fac_scale: get(dict, "fac_scale")?,
+        ren_scale: get(dict, "ren_scale")?,
+        user_weights: get(dict, "user_weights")?,
+        part: match dict.get_item("part")? {
+            Some(part) => to_part(part.extract()?)?,
+            None => Part::default(),
+        },
+        alphas_power: get(dict, "alphas_power")?,
+    })
+}
+
+fn to_part(c: char) -> PyResult<Part> {
+    Part::try_from(c).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+// Column-wise storage for a batch of events
+//
+// Particle properties and user weights are flattened, with offsets
+// marking the start of each event.
+#[derive(Default)]
+struct Batch {
+    id: Vec<i32>,
+    nparticle: Vec<i32>,
+    px: Vec<f32>,
+    py: Vec<f32>,
+    pz: Vec<f32>,
+    energy: Vec<f32>,
+    alphas: Vec<f64>,
+    pdg_code: Vec<i32>,
+    weight: Vec<f64>,
+    weight2: Vec<f64>,
+    me_weight: Vec<f64>,
+    me_weight2: Vec<f64>,
+    x1: Vec<f64>,
+    x2: Vec<f64>,
+    x1p: Vec<f64>,
+    x2p: Vec<f64>,
+    id1: Vec<i32>,
+    id2: Vec<i32>,
+    fac_scale: Vec<f64>,
+    ren_scale: Vec<f64>,
+    user_weights: Vec<f64>,
+    part: Vec<u8>,
+    alphas_power: Vec<i16>,
+    particle_offsets: Vec<i64>,
+    user_weight_offsets: Vec<i64>,
+}
+
+// Apply a macro to the names of all columns in a batch
+macro_rules! for_each_column {
+    ($m:ident, $($args:tt)*) => {
+        $m!(
+            $($args)*;
+            id, nparticle, px, py, pz, energy, alphas, pdg_code, weight,
+            weight2, me_weight, me_weight2, x1, x2, x1p, x2p, id1, id2,
+            fac_scale, ren_scale, user_weights, part, alphas_power,
+            particle_offsets, user_weight_offsets
+        )
+    };
+}
+
+impl Batch {
+    fn push(&mut self, event: Event) {
+        if self.particle_offsets.is_empty() {
+            self.particle_offsets.push(0);
+            self.user_weight_offsets.push(0);
+        }
+        self.id.push(event.id);
+        self.nparticle.push(event.nparticle);
+        self.px.extend(event.px);
+        self.py.extend(event.py);
+        self.pz.extend(event.pz);
+        self.energy.extend(event.energy);
+        self.alphas.push(event.alphas);
+        self.pdg_code.extend(event.pdg_code);
+        self.weight.push(event.weight);
+        self.weight2.push(event.weight2);
+        self.me_weight.push(event.me_weight);
+        self.me_weight2.push(event.me_weight2);
+        self.x1.push(event.x1);
+        self.x2.push(event.x2);
+        self.x1p.push(event.x1p);
+        self.x2p.push(event.x2p);
+        self.id1.push(event.id1);
+        self.id2.push(event.id2);
+        self.fac_scale.push(event.fac_scale);
+        self.ren_scale.push(event.ren_scale);
+        self.user_weights.extend(event.user_weights);
+        self.part.push(event.part.into());
+        self.alphas_power.push(event.alphas_power);
+        self.particle_offsets.push(self.px.len() as i64);
+        self.user_weight_offsets
+            .push(self.user_weights.len() as i64);
+    }
+
+    fn into_dict(self, py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+        let dict = PyDict::new(py);
+        macro_rules! set {
+            ($batch:ident; $($col:ident),*) => {
+                $(
+                    let col = PyArray1::from_vec(py, $batch.$col);
+                    dict.set_item(stringify!($col), col)?;
+                )*
+            };
+        }
+        for_each_column!(set, self);
+        Ok(dict)
+    }
+
+    fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let mut batch = Self::default();
+        macro_rules! get {
+            ($batch:ident; $($col:ident),*) => {
+                $(
+                    let Some(col) = dict.get_item(stringify!($col))? else {
+                        return Err(PyValueError::new_err(concat!(
+                            "Missing column '",
+                            stringify!($col),
+                            "'"
+                        )));
+                    };
+                    let col: PyReadonlyArray1<'_, _> = col.extract()?;
+                    $batch.$col = col.as_slice()?.to_vec();
+                )*
+            };
+        }
+        for_each_column!(get, batch);
+        Ok(batch)
+    }
+
+    fn into_events(self) -> PyResult<Vec<Event>> {
+        let nevents = self.id.len();
+        let bad_len = || PyValueError::new_err("Inconsistent column lengths");
+        if self.particle_offsets.len() != nevents + 1
+            || self.user_weight_offsets.len() != nevents + 1
+        {
+            return Err(bad_len());
+        }
+        let range = |offsets: &[i64], i: usize| {
+            let start = usize::try_from(offsets[i]).ok()?;
+            let end = usize::try_from(offsets[i + 1]).ok()?;
+            (start <= end).then_some(start..end)
+        };
+        let mut events = Vec::with_capacity(nevents);
+        for i in 0..nevents {
+            let p = range(&self.particle_offsets, i).ok_or_else(bad_len)?;
+            let w = range(&self.user_weight_offsets, i).ok_or_else(bad_len)?;
+            let particles = |col: &[f32]| col.get(p.clone()).map(<[_]>::to_vec);
+            let event = Event {
+                id: self.id[i],
+                nparticle: *self.nparticle.get(i).ok_or_else(bad_len)?,
+                px: particles(&self.px).ok_or_else(bad_len)?,
+                py: particles(&self.py).ok_or_else(bad_len)?,
+                pz: particles(&self.pz).ok_or_else(bad_len)?,
+                energy: particles(&self.energy).ok_or_else(bad_len)?,
+                alphas: *self.alphas.get(i).ok_or_else(bad_len)?,
+                pdg_code: self
+                    .pdg_code
+                    .get(p.clone())
+                    .ok_or_else(bad_len)?
+                    .to_vec(),
+                weight: *self.weight.get(i).ok_or_else(bad_len)?,
+                weight2: *self.weight2.get(i).ok_or_else(bad_len)?,
+                me_weight: *self.me_weight.get(i).ok_or_else(bad_len)?,
+                me_weight2: *self.me_weight2.get(i).ok_or_else(bad_len)?,
+                x1: *self.x1.get(i).ok_or_else(bad_len)?,
+                x2: *self.x2.get(i).ok_or_else(bad_len)?,
+                x1p: *self.x1p.get(i).ok_or_else(bad_len)?,
+                x2p: *self.x2p.get(i).ok_or_else(bad_len)?,
+                id1: *self.id1.get(i).ok_or_else(bad_len)?,
+                id2: *self.id2.get(i).ok_or_else(bad_len)?,
+                fac_scale: *self.fac_scale.get(i).ok_or_else(bad_len)?,
+                ren_scale: *self.ren_scale.get(i).ok_or_else(bad_len)?,
+                user_weights: self
+                    .user_weights
+                    .get(w)
+                    .ok_or_else(bad_len)?
+                    .to_vec(),
+                part: to_part(char::from(
+                    *self.part.get(i).ok_or_else(bad_len)?,
+                ))?,
+                alphas_power: *self.alphas_power.get(i).ok_or_else(bad_len)?,
+            };
+            events.push(event);
+        }
+        Ok(events)
+    }
+}
+
+#[pymodule]
+#[pyo3(name = "ntuple")]
+fn ntuple_python(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Reader>()?;
+    m.add_class::<Writer>()?;
+    m.add("NTupleError", m.py().get_type::<NTupleError>())?;
+    Ok(())
+}
@@ -0,0 +1,73 @@
+from pathlib import Path
+
+import numpy as np
+import pytest
+
+import ntuple
+
+TEST_DATA = Path(__file__).parents[2] / "test_data"
+FILES = sorted(TEST_DATA.glob("*.root"))
+
+
+@pytest.mark.parametrize("path", FILES)
+def test_round_trip(path, tmp_path):
+    events = list(ntuple.Reader(path))
+    assert len(events) == len(ntuple.Reader(path))
+
+    out = tmp_path / "out.root"
+    with ntuple.Writer(out) as writer:
+        for event in events:
+            writer.write(event)
+    assert list(ntuple.Reader(out)) == events
+
+
+@pytest.mark.parametrize("path", FILES)
+def test_from_bytes(path):
+    events = list(ntuple.Reader.from_bytes(path.read_bytes()))
+    assert events == list(ntuple.Reader(path))
+
+
+@pytest.mark.parametrize("path", FILES)
+def test_batch(path, tmp_path):
+    events = list(ntuple.Reader(path))
+    reader = ntuple.Reader(path)
+    batches = []
+    while (batch := reader.read_batch(7)) is not None:
+        batches.append(batch)
+    assert sum(len(batch["id"]) for batch in batches) == len(events)
+
+    first = batches[0]
+    for i, event in enumerate(events[: len(first["id"])]):
+        start, end = first["particle_offsets"][i : i + 2]
+        assert event["weight"] == first["weight"][i]
+        assert event["pdg_code"] == list(first["pdg_code"][start:end])
+        assert np.allclose(event["px"], first["px"][start:end])
+        assert event["part"] == first["part"].view("S1")[i].decode()
+
+    out = tmp_path / "out.root"
+    with ntuple.Writer(out) as writer:
+        for batch in batches:
+            writer.write_batch(batch)
+    assert list(ntuple.Reader(out)) == events
+
+
+def test_defaults(tmp_path):
+    out = tmp_path / "out.root"
+    with ntuple.Writer(out, "title") as writer:
+        writer.write({"id": 1, "px": [1.0], "py": [0.0], "pz": [0.0],
+                      "energy": [1.0], "pdg_code": [21], "part": "V"})
+    (event,) = ntuple.Reader(out)
+    assert event["nparticle"] == 1
+    assert event["part"] == "V"
+    assert event["weight"] == 0.0
+
+
+def test_errors(tmp_path):
+    with pytest.raises(ntuple.NTupleError):
+        ntuple.Reader(tmp_path / "missing.root")
+    writer = ntuple.Writer(tmp_path / "out.root")
+    with pytest.raises(ValueError):
+        writer.write({"part": "X"})
+    writer.close()
+    with pytest.raises(ntuple.NTupleError):
+        writer.write({})
@@ -0,0 +1,365 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use ntuple::{
+    compression::{compress, decompress, Encoder},
+    event::Part,
+    filter::Filter,
+    json::{JsonLinesReader, JsonLinesWriter},
+    split::{split, Distribution, SplitOptions, SplitSize},
+    xsection::{CrossSection, XSResult},
+    Event, EventSource, Reader, Writer,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Opt {
+    #[command(subcommand)]
+    cmd: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Split an ntuple file into several smaller files
+    Split(SplitArgs),
+    /// Keep only the events passing a filter expression
+    Skim(SkimArgs),
+    /// Convert between event formats
+    Convert(ConvertArgs),
+    /// Show the cross section for each event type
+    #[command(name = "xsection", alias = "xs")]
+    XSection(XSectionArgs),
+}
+
+#[derive(Debug, Args)]
+struct SplitArgs {
+    /// Number of parts
+    #[arg(short = 'n', long, required_unless_present = "chunk_size")]
+    parts: Option<usize>,
+
+    /// Number of events per part
+    #[arg(short, long, conflicts_with = "parts")]
+    chunk_size: Option<usize>,
+
+    /// Distribute events over the parts in turn
+    #[arg(short, long)]
+    round_robin: bool,
+
+    /// Keep consecutive events with the same id in the same part
+    #[arg(short, long)]
+    keep_groups: bool,
+
+    /// Output prefix [default: input file name without extension]
+    ///
+    /// Output files are named <PREFIX>.<N>.root
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Input file
+    input: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct SkimArgs {
+    /// Filter expression, e.g. "nparticle >= 4 && part == 'V' && pt(0) > 30"
+    #[arg(short, long)]
+    expr: Filter,
+
+    /// Title of the output `TTree`
+    #[arg(short, long, default_value = "")]
+    title: String,
+
+    /// Input file
+    input: PathBuf,
+
+    /// Output file
+    output: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct ConvertArgs {
+    /// Input format [default: inferred from the file extension]
+    #[arg(long, value_enum)]
+    from: Option<Format>,
+
+    /// Output format [default: inferred from the file extension]
+    #[arg(long, value_enum)]
+    to: Option<Format>,
+
+    /// Compression of the output [default: inferred from the file extension]
+    ///
+    /// Compression is only supported for text formats. Compressed
+    /// input is detected automatically.
+    #[arg(short, long, value_enum)]
+    compression: Option<Compression>,
+
+    /// Title of the output `TTree` for ROOT output
+    #[arg(short, long, default_value = "")]
+    title: String,
+
+    /// Input file
+    input: PathBuf,
+
+    /// Output file
+    output: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct XSectionArgs {
+    /// Input files
+    #[arg(required = true)]
+    inputs: Vec<PathBuf>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, ValueEnum)]
+enum Format {
+    /// ROOT ntuple
+    Root,
+    /// HepMC2 (IO_GenEvent)
+    #[value(name = "hepmc2", alias = "hepmc")]
+    HepMC2,
+    /// JSON lines
+    #[value(alias = "jsonl")]
+    Json,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, ValueEnum)]
+enum Compression {
+    #[default]
+    None,
+    #[value(alias = "gz")]
+    Gzip,
+    #[value(alias = "zst")]
+    Zstd,
+}
+
+impl From<Compression> for ntuple::compression::Compression {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::None => Self::None,
+            Compression::Gzip => Self::Gzip,
+            Compression::Zstd => Self::Zstd,
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let opt = Opt::parse();
+    match opt.cmd {
+        Command::Split(args) => run_split(args),
+        Command::Skim(args) => run_skim(args),
+        Command::Convert(args) => run_convert(args),
+        Command::XSection(args) => run_xsection(args),
+    }
+}
+
+fn run_split(args: SplitArgs) -> Result<()> {
+    let size = match (args.parts, args.chunk_size) {
+        (Some(n), _) => SplitSize::Parts(n),
+        (None, Some(n)) => SplitSize::Chunks(n),
+        (None, None) => unreachable!(),
+    };
+    let distribution = if args.round_robin {
+        Distribution::RoundRobin
+    } else {
+        Distribution::Contiguous
+    };
+    let opts = SplitOptions {
+        distribution,
+        keep_groups: args.keep_groups,
+        ..SplitOptions::new(size)
+    };
+    let prefix = args.output.unwrap_or_else(|| args.input.with_extension(""));
+    let parts = split(&args.input, prefix, &opts)
+        .with_context(|| format!("Failed to split {:?}", args.input))?;
+    for part in parts {
+        println!("{}", part.display());
+    }
+    Ok(())
+}
+
+fn run_skim(args: SkimArgs) -> Result<()> {
+    let reader = Reader::new(&args.input)
+        .with_context(|| format!("Failed to read from {:?}", args.input))?;
+    let mut writer = Writer::new(&args.output, &args.title)
+        .with_context(|| format!("Failed to write to {:?}", args.output))?;
+    for event in reader {
+        let event = event
+            .with_context(|| format!("Failed to read from {:?}", args.input))?;
+        if args.expr.matches(&event) {
+            writer.write(&event).with_context(|| {
+                format!("Failed to write to {:?}", args.output)
+            })?;
+        }
+    }
+    writer
+        .finish()
+        .with_context(|| format!("Failed to write to {:?}", args.output))
+}
+
+fn run_convert(args: ConvertArgs) -> Result<()> {
+    let (ext_compression, ext_format) = parse_extension(&args.input);
+    let Some(from) = args.from.or(ext_format) else {
+        bail!("Failed to determine format of {:?}, use --from", args.input)
+    };
+    if from == Format::Root && ext_compression != Compression::None {
+        bail!("Compressed ROOT files are not supported");
+    }
+    let (ext_compression, ext_format) = parse_extension(&args.output);
+    let Some(to) = args.to.or(ext_format) else {
+        bail!("Failed to determine format of {:?}, use --to", args.output)
+    };
+    let compression = args.compression.unwrap_or(ext_compression);
+    if to == Format::Root && compression != Compression::None {
+        bail!("Compressed ROOT output is not supported");
+    }
+
+    // text output, completed after all events have been written
+    let mut text = None;
+    let mut output =
+        Output::create(&args.output, to, compression, &args.title, &mut text)
+            .with_context(|| format!("Failed to create {:?}", args.output))?;
+    let input_err = || format!("Failed to read from {:?}", args.input);
+    let output_err = || format!("Failed to write to {:?}", args.output);
+    match from {
+        Format::Root => {
+            let reader = Reader::new(&args.input).with_context(input_err)?;
+            for event in reader {
+                let event = event.with_context(input_err)?;
+                output.write(&event).with_context(output_err)?;
+            }
+        }
+        Format::HepMC2 => {
+            let input = open_text(&args.input).with_context(input_err)?;
+            for event in hepmc2::Reader::new(input).events() {
+                let event = event.with_context(input_err)?;
+                output.write(&event).with_context(output_err)?;
+            }
+        }
+        Format::Json => {
+            let input = open_text(&args.input).with_context(input_err)?;
+            for event in JsonLinesReader::new(input) {
+                let event = event.with_context(input_err)?;
+                output.write(&event).with_context(output_err)?;
+            }
+        }
+    }
+    output.finish().with_context(output_err)?;
+    if let Some(text) = text {
+        text.finish().with_context(output_err)?;
+    }
+    Ok(())
+}
+
+fn run_xsection(args: XSectionArgs) -> Result<()> {
+    for input in args.inputs {
+        let reader = Reader::new(&input)
+            .with_context(|| format!("Failed to read from {input:?}"))?;
+        let mut xs = CrossSection::new();
+        for event in reader {
+            let event = event
+                .with_context(|| format!("Failed to read from {input:?}"))?;
+            xs.add(&event);
+        }
+        println!("{}", input.display());
+        println!(
+            "{:<6} {:>10} {:>10} {:>14} {:>14}",
+            "part", "events", "ids", "xs", "error"
+        );
+        let print_row = |name: &str, res: XSResult| {
+            println!(
+                "{name:<6} {:>10} {:>10} {:>14.6e} {:>14.6e}",
+                res.nevents, res.nids, res.xs, res.err
+            )
+        };
+        for part in Part::ALL {
+            let res = xs.part(part);
+            if res.nevents > 0 {
+                print_row(&part.to_string(), res);
+            }
+        }
+        print_row("total", xs.total());
+    }
+    Ok(())
+}
+
+// Determine compression and format from the file extension
+fn parse_extension(path: &Path) -> (Compression, Option<Format>) {
+    let ext = |path: &Path| {
+        path.extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+    };
+    let (compression, path) = match ext(path).as_deref() {
+        Some("gz") => (Compression::Gzip, path.with_extension("")),
+        Some("zst") => (Compression::Zstd, path.with_extension("")),
+        _ => (Compression::None, path.to_owned()),
+    };
+    let format = match ext(&path).as_deref() {
+        Some("root") => Some(Format::Root),
+        Some("hepmc" | "hepmc2") => Some(Format::HepMC2),
+        Some("json" | "jsonl" | "ndjson") => Some(Format::Json),
+        _ => None,
+    };
+    (compression, format)
+}
+
+// Open a text file, decompressing it if necessary
+fn open_text(path: &Path) -> Result<Box<dyn BufRead>> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(decompress(reader)?)
+}
+
+type TextWriter = Encoder<BufWriter<File>>;
+
+enum Output<'a> {
+    Root(Writer),
+    HepMC2(hepmc2::Writer<&'a mut TextWriter>),
+    Json(JsonLinesWriter<&'a mut TextWriter>),
+}
+
+impl<'a> Output<'a> {
+    // For text formats, the (compressed) file is stored in `text`
+    fn create(
+        path: &Path,
+        format: Format,
+        compression: Compression,
+        title: &str,
+        text: &'a mut Option<TextWriter>,
+    ) -> Result<Self> {
+        if format == Format::Root {
+            return Ok(Self::Root(Writer::new(path, title)?));
+        }
+        let file = BufWriter::new(File::create(path)?);
+        let writer = text.insert(compress(file, compression.into())?);
+        let output = match format {
+            Format::Root => unreachable!(),
+            Format::HepMC2 => Self::HepMC2(hepmc2::Writer::try_from(writer)?),
+            Format::Json => Self::Json(JsonLinesWriter::new(writer)),
+        };
+        Ok(output)
+    }
+
+    fn write(&mut self, event: &Event) -> Result<()> {
+        match self {
+            Self::Root(writer) => writer.write(event)?,
+            Self::HepMC2(writer) => writer.write(&event.into())?,
+            Self::Json(writer) => writer.write(event)?,
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<()> {
+        match self {
+            Self::Root(writer) => writer.finish()?,
+            Self::HepMC2(writer) => writer.finish()?,
+            Self::Json(mut writer) => writer.flush()?,
+        }
+        Ok(())
+    }
+}
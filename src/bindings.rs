@@ -1,3 +1,9 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 include!(concat!(env!("OUT_DIR"), "/ntuple.rs"));
+
+/// Message of the last C++ exception caught in the current thread
+pub(crate) fn last_error() -> String {
+    let msg = unsafe { std::ffi::CStr::from_ptr(ntuple_last_error()) };
+    msg.to_string_lossy().into_owned()
+}
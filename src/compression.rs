@@ -0,0 +1,136 @@
+//! Transparent compression for text-based event formats
+use std::io::{self, BufRead, BufReader, Write};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// Wrap a reader, decompressing gzip and zstd data
+///
+/// The compression format is detected from the first bytes of the
+/// input. Uncompressed data is passed through unchanged.
+pub fn decompress<'a, R: BufRead + 'a>(
+    mut reader: R,
+) -> io::Result<Box<dyn BufRead + 'a>> {
+    let start = reader.fill_buf()?;
+    let reader: Box<dyn BufRead> = if start.starts_with(&GZIP_MAGIC) {
+        let decoder = flate2::bufread::MultiGzDecoder::new(reader);
+        Box::new(BufReader::new(decoder))
+    } else if start.starts_with(&ZSTD_MAGIC) {
+        Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?))
+    } else {
+        Box::new(reader)
+    };
+    Ok(reader)
+}
+
+/// Wrap a writer, compressing all data written to it
+///
+/// The compressed stream has to be completed with
+/// [finish](Encoder::finish).
+pub fn compress<W: Write>(
+    writer: W,
+    compression: Compression,
+) -> io::Result<Encoder<W>> {
+    let encoder = match compression {
+        Compression::None => Encoder::None(writer),
+        Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+            writer,
+            flate2::Compression::default(),
+        )),
+        Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(writer, 0)?),
+    };
+    Ok(encoder)
+}
+
+/// Writer compressing all data written to it, see [compress]
+///
+/// Dropping an encoder without calling [finish](Self::finish) may
+/// leave truncated output without reporting an error.
+pub enum Encoder<W: Write> {
+    None(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> Encoder<W> {
+    /// Complete the compressed stream and flush the underlying writer
+    pub fn finish(self) -> io::Result<W> {
+        let mut writer = match self {
+            Self::None(writer) => writer,
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Zstd(encoder) => encoder.finish()?,
+        };
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::None(writer) => writer.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::None(writer) => writer.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn round_trip() {
+        const DATA: &[u8] = b"HepMC::Version 2.06.09\n";
+        for compression in
+            [Compression::None, Compression::Gzip, Compression::Zstd]
+        {
+            let mut buf = Vec::new();
+            let mut writer = compress(&mut buf, compression).unwrap();
+            writer.write_all(DATA).unwrap();
+            writer.finish().unwrap();
+            let mut reader = decompress(buf.as_slice()).unwrap();
+            let mut read = Vec::new();
+            reader.read_to_end(&mut read).unwrap();
+            assert_eq!(read, DATA);
+        }
+    }
+
+    struct Full;
+
+    impl Write for Full {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::StorageFull.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn finish_error() {
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            // even an empty stream has a header and a trailer
+            let writer = compress(Full, compression).unwrap();
+            assert!(writer.finish().is_err());
+        }
+    }
+}
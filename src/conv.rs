@@ -10,18 +10,13 @@ impl From<&Event> for hepmc2::Event {
         let nparticles = ev.nparticle as usize;
         let mut particles = Vec::with_capacity(1 + nparticles);
         for i in 0..nparticles {
-            let p = [
-                ev.energy[i] as f64,
-                ev.px[i] as f64,
-                ev.py[i] as f64,
-                ev.pz[i] as f64,
-            ];
+            let p = ev.momentum(i);
             let p = Particle {
                 id: ev.pdg_code[i],
-                p: hepmc2::event::FourVector(p),
+                p: hepmc2::event::FourVector(p.into()),
                 m: 0.,
-                theta: theta(p),
-                phi: phi(p),
+                theta: p.theta(),
+                phi: p.phi(),
                 status: OUTGOING_STATUS,
                 ..Default::default()
             };
@@ -138,19 +133,3 @@ impl From<&hepmc2::Event> for Event {
         }
     }
 }
-
-fn phi(p: [f64; 4]) -> f64 {
-    p[1].atan2(p[2])
-}
-
-fn theta(p: [f64; 4]) -> f64 {
-    pt(p).atan2(p[3])
-}
-
-fn pt2(p: [f64; 4]) -> f64 {
-    p[1] * p[1] + p[2] * p[2]
-}
-
-fn pt(p: [f64; 4]) -> f64 {
-    pt2(p).sqrt()
-}
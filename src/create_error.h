@@ -8,4 +8,7 @@ typedef enum {
   EXCEPTION
 } NTupleCreateError;
 
+/* Message of the last exception caught in the current thread */
+char const* ntuple_last_error(void);
+
 #endif /* NTUPLE_CREATE_ERROR_H */
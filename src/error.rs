@@ -0,0 +1,114 @@
+//! Crate-level error type
+use std::io;
+
+use thiserror::Error;
+
+#[cfg(feature = "json")]
+use crate::json;
+#[cfg(feature = "unweight")]
+use crate::unweight;
+use crate::{
+    event, filter, frame, hist, reader, resample, run_info, split, writer,
+};
+
+/// Any error that can occur in this crate
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error(transparent)]
+    CreateReader(#[from] reader::CreateError),
+    #[error(transparent)]
+    CreateWriter(#[from] writer::CreateError),
+    #[error(transparent)]
+    Read(#[from] reader::ReadError),
+    #[error(transparent)]
+    Leaf(#[from] reader::LeafError),
+    #[error(transparent)]
+    Write(#[from] writer::WriteError),
+    #[error(transparent)]
+    Conversion(#[from] event::ConversionError),
+    #[error(transparent)]
+    RunInfo(#[from] run_info::RunInfoError),
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Filter(#[from] filter::ParseError),
+    #[error(transparent)]
+    Frame(#[from] frame::FrameError),
+    #[error(transparent)]
+    Axis(#[from] hist::AxisError),
+    #[error(transparent)]
+    Split(#[from] split::SplitError),
+    #[error(transparent)]
+    Resample(#[from] resample::ResampleError),
+    #[cfg(feature = "unweight")]
+    #[error(transparent)]
+    Unweight(#[from] unweight::UnweightError),
+    #[cfg(feature = "json")]
+    #[error(transparent)]
+    Json(#[from] json::JsonError),
+    /// [HepMCError](crate::hepmc::HepMCError) with any source and sink
+    #[cfg(feature = "hepmc2")]
+    #[error(transparent)]
+    HepMC(Box<dyn std::error::Error + Send + Sync>),
+}
+
+#[cfg(feature = "hepmc2")]
+impl<R, W> From<crate::hepmc::HepMCError<R, W>> for Error
+where
+    R: std::error::Error + Send + Sync + 'static,
+    W: std::error::Error + Send + Sync + 'static,
+{
+    fn from(err: crate::hepmc::HepMCError<R, W>) -> Self {
+        Self::HepMC(Box::new(err))
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{event::Part, Reader};
+
+    fn open_and_convert(path: &str, part: char) -> Result<Part> {
+        Reader::new(path)?;
+        Ok(part.try_into()?)
+    }
+
+    #[test]
+    fn question_mark() {
+        let err = open_and_convert("nul\0byte.root", 'B').unwrap_err();
+        assert!(matches!(
+            err,
+            Error::CreateReader(reader::CreateError::NulByteInPath(_))
+        ));
+    }
+    #[test]
+    fn conversions() {
+        fn parse(expr: &str) -> Result<filter::Filter> {
+            Ok(filter::Filter::parse(expr)?)
+        }
+        assert!(matches!(parse("pt >"), Err(Error::Filter(_))));
+
+        fn read(path: &str) -> Result<Vec<u8>> {
+            Ok(std::fs::read(path)?)
+        }
+        match read("/nonexistent/ntuple.root") {
+            Err(Error::Io(err)) => {
+                assert_eq!(err.kind(), io::ErrorKind::NotFound)
+            }
+            res => panic!("Expected I/O error, got {res:?}"),
+        }
+    }
+
+    #[cfg(feature = "hepmc2")]
+    #[test]
+    fn hepmc_conversion() {
+        fn convert(input: &[u8]) -> Result<usize> {
+            Ok(crate::hepmc::from_hepmc(input, Vec::new())?)
+        }
+        let input = b"HepMC::Version 2.06.09\nHepMC::IO_GenEvent-START_EVENT_LISTING\nE nonsense\n";
+        assert!(matches!(convert(input), Err(Error::HepMC(_))));
+    }
+}
@@ -1,10 +1,12 @@
 use core::slice;
+use std::{fmt, str::FromStr};
 
 use thiserror::Error;
 
-use crate::bindings::NTupleEvent;
+use crate::{bindings::NTupleEvent, reader::ReadErrorKind};
 
 #[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Event {
     pub id: i32,
     pub nparticle: i32,
@@ -31,17 +33,21 @@ pub struct Event {
     pub alphas_power: i16,
 }
 
-impl From<NTupleEvent> for Event {
-    fn from(ev: NTupleEvent) -> Self {
-        assert!(ev.nparticle >= 0);
-        assert!(ev.nuwgt >= 0);
+impl TryFrom<NTupleEvent> for Event {
+    type Error = ReadErrorKind;
+
+    fn try_from(ev: NTupleEvent) -> Result<Self, Self::Error> {
+        if ev.nparticle < 0 {
+            return Err(ReadErrorKind::NegParticleNum(ev.nparticle));
+        }
+        if ev.nuwgt < 0 {
+            return Err(ReadErrorKind::NegWeightNum(ev.nuwgt));
+        }
         let npart = ev.nparticle as usize;
         let nwgt = ev.nuwgt as usize;
-        let part = match ev.part.try_into() {
-            Ok(part) => part,
-            Err(err) => panic!("Unrecognised event type: {err}"),
-        };
-        Self {
+        let part = Part::try_from(ev.part)
+            .map_err(|_| ReadErrorKind::BadPart(ev.part))?;
+        Ok(Self {
             id: ev.id,
             nparticle: ev.nparticle,
             px: unsafe { slice::from_raw_parts(ev.px, npart) }.to_owned(),
@@ -67,11 +73,12 @@ impl From<NTupleEvent> for Event {
                 .to_owned(),
             part,
             alphas_power: ev.alphas_power,
-        }
+        })
     }
 }
 
 #[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Part {
     #[default]
     B,
@@ -81,6 +88,70 @@ pub enum Part {
     S,
 }
 
+impl Part {
+    /// All event types
+    pub const ALL: [Part; 5] = [Part::B, Part::I, Part::R, Part::V, Part::S];
+
+    /// Whether this is one of the event types defined in arXiv:1310.7439
+    ///
+    /// This is the case for all types except [Part::S].
+    pub fn is_official(self) -> bool {
+        self != Part::S
+    }
+
+    /// Perturbative order of the event type
+    ///
+    /// Born events are [PerturbativeOrder::LO], virtual, integrated
+    /// subtraction, and real events are [PerturbativeOrder::NLO].
+    /// Returns `None` for the unofficial type [Part::S].
+    pub fn perturbative_order(self) -> Option<PerturbativeOrder> {
+        use Part::*;
+        match self {
+            B => Some(PerturbativeOrder::LO),
+            I | R | V => Some(PerturbativeOrder::NLO),
+            S => None,
+        }
+    }
+
+    /// Convert from a character, rejecting unofficial event types
+    ///
+    /// In contrast to `Part::try_from`, this only accepts the event
+    /// types defined in arXiv:1310.7439.
+    pub fn try_from_strict(c: char) -> Result<Self, ConversionError> {
+        let part = Self::try_from(c)?;
+        if part.is_official() {
+            Ok(part)
+        } else {
+            Err(ConversionError::Unofficial(c))
+        }
+    }
+}
+
+/// Perturbative order of an event type
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum PerturbativeOrder {
+    LO,
+    NLO,
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+impl FromStr for Part {
+    type Err = ConversionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => c.try_into(),
+            _ => Err(ConversionError::BadLength(s.chars().count())),
+        }
+    }
+}
+
 impl From<Part> for u8 {
     fn from(p: Part) -> Self {
         use Part::*;
@@ -108,11 +179,13 @@ impl From<Part> for char {
 
 #[derive(Copy, Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum ConversionError {
-    // We also support 'S', but don't want to encourage users to use
-    // it, as it is not an official event type according to
-    // arXiv:1310.7439
-    #[error("'{0}' is not one of 'B', 'I', 'R', 'V'")]
+    #[error("'{0}' is not one of 'B', 'I', 'R', 'V', 'S'")]
     BadChar(char),
+    #[error("Expected a single character, found {0}")]
+    BadLength(usize),
+    // 'S' is not an official event type according to arXiv:1310.7439
+    #[error("'{0}' is not one of the official event types 'B', 'I', 'R', 'V'")]
+    Unofficial(char),
 }
 
 impl TryFrom<char> for Part {
@@ -151,6 +224,34 @@ impl TryFrom<i8> for Part {
     type Error = ConversionError;
 
     fn try_from(c: i8) -> Result<Self, Self::Error> {
-        c.try_into()
+        (c as u8).try_into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn part() {
+        for part in Part::ALL {
+            let s = part.to_string();
+            assert_eq!(s.parse::<Part>(), Ok(part));
+            let c = char::from(part);
+            assert_eq!(Part::try_from(c), Ok(part));
+            assert_eq!(Part::try_from(u8::from(part)), Ok(part));
+            assert_eq!(Part::try_from(i8::from(part)), Ok(part));
+            assert_eq!(Part::try_from_strict(c).is_ok(), part.is_official());
+        }
+        assert_eq!("".parse::<Part>(), Err(ConversionError::BadLength(0)));
+        assert_eq!("BV".parse::<Part>(), Err(ConversionError::BadLength(2)));
+        assert_eq!("X".parse::<Part>(), Err(ConversionError::BadChar('X')));
+        assert_eq!(
+            Part::try_from_strict('S'),
+            Err(ConversionError::Unofficial('S'))
+        );
+        assert_eq!(Part::B.perturbative_order(), Some(PerturbativeOrder::LO));
+        assert_eq!(Part::R.perturbative_order(), Some(PerturbativeOrder::NLO));
+        assert_eq!(Part::S.perturbative_order(), None);
     }
 }
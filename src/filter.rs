@@ -0,0 +1,619 @@
+//! Expressions for event selection
+//!
+//! A filter expression combines event properties with arithmetic,
+//! comparison and logical operators, for example
+//! `nparticle >= 4 && part == 'V' && pt(0) > 30`.
+//!
+//! The following event properties are available:
+//! `id`, `nparticle`, `alphas`, `weight`, `weight2`, `me_weight`,
+//! `me_weight2`, `x1`, `x2`, `x1p`, `x2p`, `id1`, `id2`, `fac_scale`,
+//! `ren_scale`, `nuwgt` (the number of user weights), `part`, and
+//! `alphas_power`.
+//!
+//! Properties of the `i`th outgoing particle are accessed with the
+//! functions `px(i)`, `py(i)`, `pz(i)`, `E(i)`, `pdg(i)`, `pt(i)`,
+//! `y(i)` (rapidity), `eta(i)` (pseudorapidity), `phi(i)`, and `m(i)`
+//! (mass). `user_weight(i)` is the `i`th user weight and `abs(x)` is
+//! the absolute value.
+//!
+//! All values are numbers. Characters in single or double quotes
+//! stand for their character codes, so they can be compared to
+//! `part`. Comparisons and logical operators return 1 for true and 0
+//! for false, and any non-zero value counts as true. Accessing a
+//! particle or user weight that does not exist gives a NaN, so
+//! comparisons involving it are false.
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::Event;
+
+/// Compiled filter expression
+#[derive(Clone, Debug, PartialEq)]
+pub struct Filter(Expr);
+
+impl Filter {
+    pub fn parse(expr: &str) -> Result<Self, ParseError> {
+        let tokens = tokenize(expr)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+        };
+        let expr = parser.parse_or()?;
+        if let Some((pos, token)) = parser.peek() {
+            return Err(ParseError::UnexpectedToken(token.to_string(), pos));
+        }
+        Ok(Self(expr))
+    }
+
+    /// Evaluate the expression for the given event
+    pub fn eval(&self, event: &Event) -> f64 {
+        self.0.eval(event)
+    }
+
+    /// Check whether the event passes the filter
+    pub fn matches(&self, event: &Event) -> bool {
+        is_true(self.eval(event))
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// Iterator over the events passing a [Filter]
+///
+/// Errors are passed through unchanged.
+#[derive(Clone, Debug)]
+pub struct Filtered<I> {
+    events: I,
+    filter: Filter,
+}
+
+impl<I> Filtered<I> {
+    pub fn new(events: I, filter: Filter) -> Self {
+        Self { events, filter }
+    }
+
+    pub fn into_inner(self) -> I {
+        self.events
+    }
+}
+
+impl<I, E> Iterator for Filtered<I>
+where
+    I: Iterator<Item = Result<Event, E>>,
+{
+    type Item = Result<Event, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.find(|event| match event {
+            Ok(event) => self.filter.matches(event),
+            Err(_) => true,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ParseError {
+    #[error("Unexpected character '{0}' at position {1}")]
+    UnexpectedChar(char, usize),
+    #[error("Unterminated character literal at position {0}")]
+    UnterminatedChar(usize),
+    #[error("Invalid number `{0}` at position {1}")]
+    BadNumber(String, usize),
+    #[error("Unexpected `{0}` at position {1}")]
+    UnexpectedToken(String, usize),
+    #[error("Unexpected end of expression")]
+    UnexpectedEnd,
+    #[error("Unknown event property `{0}` at position {1}")]
+    UnknownVariable(String, usize),
+    #[error("Unknown function `{0}` at position {1}")]
+    UnknownFunction(String, usize),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Var {
+    Id,
+    NParticle,
+    Alphas,
+    Weight,
+    Weight2,
+    MeWeight,
+    MeWeight2,
+    X1,
+    X2,
+    X1p,
+    X2p,
+    Id1,
+    Id2,
+    FacScale,
+    RenScale,
+    NUWgt,
+    Part,
+    AlphasPower,
+}
+
+impl Var {
+    fn from_name(name: &str) -> Option<Self> {
+        use Var::*;
+        let var = match name {
+            "id" => Id,
+            "nparticle" => NParticle,
+            "alphas" => Alphas,
+            "weight" => Weight,
+            "weight2" => Weight2,
+            "me_weight" | "me_wgt" => MeWeight,
+            "me_weight2" | "me_wgt2" => MeWeight2,
+            "x1" => X1,
+            "x2" => X2,
+            "x1p" => X1p,
+            "x2p" => X2p,
+            "id1" => Id1,
+            "id2" => Id2,
+            "fac_scale" => FacScale,
+            "ren_scale" => RenScale,
+            "nuwgt" => NUWgt,
+            "part" => Part,
+            "alphas_power" | "alphasPower" => AlphasPower,
+            _ => return None,
+        };
+        Some(var)
+    }
+
+    fn eval(self, ev: &Event) -> f64 {
+        use Var::*;
+        match self {
+            Id => ev.id.into(),
+            NParticle => ev.nparticle.into(),
+            Alphas => ev.alphas,
+            Weight => ev.weight,
+            Weight2 => ev.weight2,
+            MeWeight => ev.me_weight,
+            MeWeight2 => ev.me_weight2,
+            X1 => ev.x1,
+            X2 => ev.x2,
+            X1p => ev.x1p,
+            X2p => ev.x2p,
+            Id1 => ev.id1.into(),
+            Id2 => ev.id2.into(),
+            FacScale => ev.fac_scale,
+            RenScale => ev.ren_scale,
+            NUWgt => ev.user_weights.len() as f64,
+            Part => u8::from(ev.part).into(),
+            AlphasPower => ev.alphas_power.into(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Func {
+    Px,
+    Py,
+    Pz,
+    E,
+    Pdg,
+    Pt,
+    Y,
+    Eta,
+    Phi,
+    M,
+    UserWeight,
+    Abs,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Self> {
+        use Func::*;
+        let func = match name {
+            "px" => Px,
+            "py" => Py,
+            "pz" => Pz,
+            "E" | "energy" => E,
+            "pdg" | "kf" => Pdg,
+            "pt" => Pt,
+            "y" => Y,
+            "eta" => Eta,
+            "phi" => Phi,
+            "m" => M,
+            "user_weight" => UserWeight,
+            "abs" => Abs,
+            _ => return None,
+        };
+        Some(func)
+    }
+
+    fn eval(self, arg: f64, ev: &Event) -> f64 {
+        use Func::*;
+        if self == Abs {
+            return arg.abs();
+        }
+        if arg.is_nan() || arg < 0. {
+            return f64::NAN;
+        }
+        let i = arg as usize;
+        if self == UserWeight {
+            return ev.user_weights.get(i).copied().unwrap_or(f64::NAN);
+        }
+        let npart = [
+            ev.px.len(),
+            ev.py.len(),
+            ev.pz.len(),
+            ev.energy.len(),
+            ev.pdg_code.len(),
+        ];
+        if npart.into_iter().any(|n| i >= n) {
+            return f64::NAN;
+        }
+        let p = ev.momentum(i);
+        match self {
+            Px => p.px(),
+            Py => p.py(),
+            Pz => p.pz(),
+            E => p.e(),
+            Pdg => ev.pdg_code[i].into(),
+            Pt => p.pt(),
+            Y => p.rapidity(),
+            Eta => p.pseudorapidity(),
+            Phi => p.phi(),
+            M => p.m(),
+            UserWeight | Abs => unreachable!(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Num(f64),
+    Var(Var),
+    Call(Func, Box<Expr>),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    BinOp(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, ev: &Event) -> f64 {
+        use BinOp::*;
+        match self {
+            Expr::Num(x) => *x,
+            Expr::Var(var) => var.eval(ev),
+            Expr::Call(func, arg) => func.eval(arg.eval(ev), ev),
+            Expr::Neg(arg) => -arg.eval(ev),
+            Expr::Not(arg) => from_bool(!is_true(arg.eval(ev))),
+            Expr::BinOp(Or, lhs, rhs) => {
+                from_bool(is_true(lhs.eval(ev)) || is_true(rhs.eval(ev)))
+            }
+            Expr::BinOp(And, lhs, rhs) => {
+                from_bool(is_true(lhs.eval(ev)) && is_true(rhs.eval(ev)))
+            }
+            Expr::BinOp(op, lhs, rhs) => {
+                let lhs = lhs.eval(ev);
+                let rhs = rhs.eval(ev);
+                match op {
+                    Eq => from_bool(lhs == rhs),
+                    Ne => from_bool(lhs != rhs),
+                    Lt => from_bool(lhs < rhs),
+                    Le => from_bool(lhs <= rhs),
+                    Gt => from_bool(lhs > rhs),
+                    Ge => from_bool(lhs >= rhs),
+                    Add => lhs + rhs,
+                    Sub => lhs - rhs,
+                    Mul => lhs * rhs,
+                    Div => lhs / rhs,
+                    Or | And => unreachable!(),
+                }
+            }
+        }
+    }
+}
+
+fn is_true(x: f64) -> bool {
+    x != 0. && !x.is_nan()
+}
+
+fn from_bool(b: bool) -> f64 {
+    if b {
+        1.
+    } else {
+        0.
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token<'a> {
+    Num(f64),
+    Ident(&'a str),
+    Op(&'static str),
+}
+
+impl std::fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Num(x) => write!(f, "{x}"),
+            Token::Ident(s) => write!(f, "{s}"),
+            Token::Op(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+// longer operators have to come first
+const OPERATORS: [&str; 15] = [
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "!", "+", "-", "*", "/", "(",
+    ")",
+];
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token<'_>)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while let Some(c) = s[pos..].chars().next() {
+        let rest = &s[pos..];
+        if c.is_whitespace() {
+            pos += c.len_utf8();
+        } else if c == '\'' || c == '"' {
+            let mut chars = rest[1..].chars();
+            match (chars.next(), chars.next()) {
+                (Some(ch), Some(end)) if end == c => {
+                    tokens.push((pos, Token::Num(u32::from(ch).into())));
+                    pos += 2 + ch.len_utf8();
+                }
+                _ => return Err(ParseError::UnterminatedChar(pos)),
+            }
+        } else if c.is_ascii_digit() || c == '.' {
+            let len = number_len(rest);
+            let num = &rest[..len];
+            let Ok(num) = num.parse() else {
+                return Err(ParseError::BadNumber(num.to_owned(), pos));
+            };
+            tokens.push((pos, Token::Num(num)));
+            pos += len;
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push((pos, Token::Ident(&rest[..len])));
+            pos += len;
+        } else if let Some(op) =
+            OPERATORS.iter().find(|op| rest.starts_with(*op))
+        {
+            tokens.push((pos, Token::Op(op)));
+            pos += op.len();
+        } else {
+            return Err(ParseError::UnexpectedChar(c, pos));
+        }
+    }
+    Ok(tokens)
+}
+
+fn number_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut len = 0;
+    let digits = |len: &mut usize| {
+        while bytes.get(*len).is_some_and(|b| b.is_ascii_digit()) {
+            *len += 1;
+        }
+    };
+    digits(&mut len);
+    if bytes.get(len) == Some(&b'.') {
+        len += 1;
+        digits(&mut len);
+    }
+    if matches!(bytes.get(len), Some(b'e' | b'E')) {
+        let mut exp_len = len + 1;
+        if matches!(bytes.get(exp_len), Some(b'+' | b'-')) {
+            exp_len += 1;
+        }
+        if bytes.get(exp_len).is_some_and(|b| b.is_ascii_digit()) {
+            len = exp_len;
+            digits(&mut len);
+        }
+    }
+    len
+}
+
+struct Parser<'a, 'b> {
+    tokens: &'b [(usize, Token<'a>)],
+    pos: usize,
+}
+
+impl Parser<'_, '_> {
+    fn peek(&self) -> Option<(usize, &Token<'_>)> {
+        self.tokens.get(self.pos).map(|(pos, t)| (*pos, t))
+    }
+
+    fn next_op(&mut self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some((_, Token::Op(op))) if ops.contains(op) => {
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    fn expect_op(&mut self, op: &'static str) -> Result<(), ParseError> {
+        if self.next_op(&[op]).is_some() {
+            return Ok(());
+        }
+        match self.peek() {
+            Some((pos, token)) => {
+                Err(ParseError::UnexpectedToken(token.to_string(), pos))
+            }
+            None => Err(ParseError::UnexpectedEnd),
+        }
+    }
+
+    fn parse_binary(
+        &mut self,
+        ops: &[&'static str],
+        mut operand: impl FnMut(&mut Self) -> Result<Expr, ParseError>,
+    ) -> Result<Expr, ParseError> {
+        let mut lhs = operand(self)?;
+        while let Some(op) = self.next_op(ops) {
+            let rhs = operand(self)?;
+            lhs = Expr::BinOp(bin_op(op), Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&["||"], Self::parse_and)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&["&&"], Self::parse_not)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ParseError> {
+        if self.next_op(&["!"]).is_some() {
+            let arg = self.parse_not()?;
+            return Ok(Expr::Not(Box::new(arg)));
+        }
+        self.parse_cmp()
+    }
+
+    fn parse_cmp(&mut self) -> Result<Expr, ParseError> {
+        let lhs = self.parse_sum()?;
+        let Some(op) = self.next_op(&["==", "!=", "<=", ">=", "<", ">"]) else {
+            return Ok(lhs);
+        };
+        let rhs = self.parse_sum()?;
+        Ok(Expr::BinOp(bin_op(op), Box::new(lhs), Box::new(rhs)))
+    }
+
+    fn parse_sum(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&["+", "-"], Self::parse_product)
+    }
+
+    fn parse_product(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&["*", "/"], Self::parse_unary)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if self.next_op(&["-"]).is_some() {
+            let arg = self.parse_unary()?;
+            return Ok(Expr::Neg(Box::new(arg)));
+        }
+        if self.next_op(&["+"]).is_some() {
+            return self.parse_unary();
+        }
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, ParseError> {
+        let Some((pos, token)) = self.tokens.get(self.pos) else {
+            return Err(ParseError::UnexpectedEnd);
+        };
+        self.pos += 1;
+        match *token {
+            Token::Num(x) => Ok(Expr::Num(x)),
+            Token::Op("(") => {
+                let expr = self.parse_or()?;
+                self.expect_op(")")?;
+                Ok(expr)
+            }
+            Token::Op(op) => {
+                Err(ParseError::UnexpectedToken(op.to_owned(), *pos))
+            }
+            Token::Ident(name) => {
+                if self.next_op(&["("]).is_none() {
+                    return match Var::from_name(name) {
+                        Some(var) => Ok(Expr::Var(var)),
+                        None => Err(ParseError::UnknownVariable(
+                            name.to_owned(),
+                            *pos,
+                        )),
+                    };
+                }
+                let Some(func) = Func::from_name(name) else {
+                    return Err(ParseError::UnknownFunction(
+                        name.to_owned(),
+                        *pos,
+                    ));
+                };
+                let arg = self.parse_or()?;
+                self.expect_op(")")?;
+                Ok(Expr::Call(func, Box::new(arg)))
+            }
+        }
+    }
+}
+
+fn bin_op(op: &str) -> BinOp {
+    use BinOp::*;
+    match op {
+        "||" => Or,
+        "&&" => And,
+        "==" => Eq,
+        "!=" => Ne,
+        "<" => Lt,
+        "<=" => Le,
+        ">" => Gt,
+        ">=" => Ge,
+        "+" => Add,
+        "-" => Sub,
+        "*" => Mul,
+        "/" => Div,
+        _ => unreachable!("Unknown binary operator {op}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Part;
+
+    #[test]
+    fn filter() {
+        let event = Event {
+            nparticle: 2,
+            px: vec![30., -40.],
+            py: vec![40., -30.],
+            pz: vec![0., 0.],
+            energy: vec![50., 50.],
+            pdg_code: vec![21, 1],
+            part: Part::V,
+            weight: -1.5,
+            ..Default::default()
+        };
+        let pass = [
+            "nparticle >= 2 && part == 'V' && pt(0) > 30",
+            "pt(0) + pt(1) == 100",
+            "!(part == \"B\") || 1/0",
+            "abs(weight) > 1e0 && weight < -.5E-1",
+            "pdg(1) == 1 && -pdg(0) * 2 == -42",
+            "pt(2) > 1 || pt(2) <= 1 || 1",
+        ];
+        for expr in pass {
+            assert!(Filter::parse(expr).unwrap().matches(&event), "{expr}");
+        }
+        let fail = ["pt(2) > 1 || pt(2) <= 1", "part != 'V'", "x1 - 1 > 0"];
+        for expr in fail {
+            assert!(!Filter::parse(expr).unwrap().matches(&event), "{expr}");
+        }
+        let bad = ["pt(0", "weight >", "foo > 1", "foo(1)", "1.2.3", "'V"];
+        for expr in bad {
+            assert!(Filter::parse(expr).is_err(), "{expr}");
+        }
+    }
+}
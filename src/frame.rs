@@ -0,0 +1,210 @@
+//! Frame transformations of events
+//!
+//! Events are stored in the lab frame of a collider with the beams
+//! along the z axis. The transformations here act on the momenta of
+//! the outgoing particles. Incoming partons are only described by
+//! their momentum fractions `x1` and `x2`, which are unaffected by
+//! boosts along the beam axis.
+use thiserror::Error;
+
+use crate::{event::Part, Event, FourMomentum};
+
+// Collinear coefficients of `I` events for the first and second beam,
+// see arXiv:1310.7439
+const I_BEAM1_WEIGHTS: std::ops::Range<usize> = 2..10;
+const I_BEAM2_WEIGHTS: std::ops::Range<usize> = 10..18;
+
+impl Event {
+    /// Lorentz boost of all outgoing momenta with velocity `beta`
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `|beta| < 1`.
+    pub fn boost(&mut self, beta: [f64; 3]) {
+        self.transform(|p| p.boost(beta))
+    }
+
+    /// Rotate all outgoing momenta by `angle` around `axis`
+    ///
+    /// Only rotations around the z axis preserve the beam direction.
+    pub fn rotate(&mut self, axis: [f64; 3], angle: f64) {
+        self.transform(|p| p.rotate(axis, angle))
+    }
+
+    /// Rotate all outgoing momenta by `angle` around the beam axis
+    pub fn rotate_z(&mut self, angle: f64) {
+        self.transform(|p| p.rotate_z(angle))
+    }
+
+    /// Exchange the two beams
+    ///
+    /// Flips the z components of all outgoing momenta and swaps
+    /// `id1` with `id2`, `x1` with `x2`, and `x1p` with `x2p`. For
+    /// [Part::I] events, the user weights 2 to 9 and 10 to 17 hold
+    /// the collinear coefficients for the first and second beam,
+    /// respectively. These blocks are swapped as well.
+    pub fn flip_z(&mut self) {
+        for pz in &mut self.pz {
+            *pz = -*pz;
+        }
+        std::mem::swap(&mut self.id1, &mut self.id2);
+        std::mem::swap(&mut self.x1, &mut self.x2);
+        std::mem::swap(&mut self.x1p, &mut self.x2p);
+        if self.part == Part::I
+            && self.user_weights.len() >= I_BEAM2_WEIGHTS.end
+        {
+            let (beam1, beam2) = self.user_weights
+                [I_BEAM1_WEIGHTS.start..I_BEAM2_WEIGHTS.end]
+                .split_at_mut(I_BEAM1_WEIGHTS.len());
+            beam1.swap_with_slice(beam2);
+        }
+    }
+
+    /// Momenta of the incoming partons in the lab frame
+    ///
+    /// `beam_energy` is the energy of each of the two beams. The
+    /// first beam moves in positive z direction.
+    pub fn incoming_momenta(&self, beam_energy: f64) -> [FourMomentum; 2] {
+        let e1 = self.x1 * beam_energy;
+        let e2 = self.x2 * beam_energy;
+        [
+            FourMomentum::new(e1, 0., 0., e1),
+            FourMomentum::new(e2, 0., 0., -e2),
+        ]
+    }
+
+    /// Boost into the partonic centre-of-mass frame
+    ///
+    /// `beam_energy` is the energy of each of the two beams. Returns
+    /// the momenta of the incoming partons in the new frame. Both
+    /// incoming partons have to have positive energy, otherwise an
+    /// error is returned and the event is left unchanged.
+    pub fn to_partonic_cms(
+        &mut self,
+        beam_energy: f64,
+    ) -> Result<[FourMomentum; 2], FrameError> {
+        let [p1, p2] = self.incoming_momenta(beam_energy);
+        if !(p1.e() > 0. && p2.e() > 0.) {
+            return Err(FrameError::NoPartonicCms([p1.e(), p2.e()]));
+        }
+        let total = p1 + p2;
+        let beta = [0., 0., -total.pz() / total.e()];
+        self.boost(beta);
+        Ok([p1.boost(beta), p2.boost(beta)])
+    }
+
+    fn transform(&mut self, f: impl Fn(FourMomentum) -> FourMomentum) {
+        let nparticles = self.particles().len();
+        for i in 0..nparticles {
+            let p = f(self.momentum(i));
+            self.set_momentum(i, p);
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Error, PartialEq, PartialOrd)]
+pub enum FrameError {
+    #[error(
+        "Incoming parton energies {0:?} do not define a centre-of-mass frame"
+    )]
+    NoPartonicCms([f64; 2]),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event() -> Event {
+        let mut event = Event {
+            nparticle: 2,
+            pdg_code: vec![11, -11],
+            id1: 2,
+            id2: -2,
+            x1: 0.4,
+            x2: 0.1,
+            x1p: 0.5,
+            x2p: 0.2,
+            ..Default::default()
+        };
+        // back-to-back e+ e- in the partonic cms, boosted to the lab
+        let [p1, p2] = event.incoming_momenta(10.);
+        let total = p1 + p2;
+        let beta = [0., 0., total.pz() / total.e()];
+        let m = total.m();
+        let q1 = FourMomentum::new(m / 2., m / 2., 0., 0.).boost(beta);
+        let q2 = FourMomentum::new(m / 2., -m / 2., 0., 0.).boost(beta);
+        event.energy = vec![q1.e() as f32, q2.e() as f32];
+        event.px = vec![q1.px() as f32, q2.px() as f32];
+        event.py = vec![q1.py() as f32, q2.py() as f32];
+        event.pz = vec![q1.pz() as f32, q2.pz() as f32];
+        event
+    }
+
+    #[test]
+    fn partonic_cms() {
+        let mut ev = event();
+        let [p1, p2] = ev.to_partonic_cms(10.).unwrap();
+        assert!((p1.e() - 2.).abs() < 1e-12);
+        assert!((p1.pz() - 2.).abs() < 1e-12);
+        assert!((p2.pz() + 2.).abs() < 1e-12);
+        let total = ev.total_momentum();
+        assert!(total.pz().abs() < 1e-5);
+        assert!((total.e() - 4.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn flip() {
+        let orig = event();
+        let mut ev = orig.clone();
+        ev.flip_z();
+        assert_eq!((ev.id1, ev.id2), (-2, 2));
+        assert_eq!((ev.x1, ev.x2), (0.1, 0.4));
+        assert_eq!((ev.x1p, ev.x2p), (0.2, 0.5));
+        assert_eq!(ev.pz, orig.pz.iter().map(|pz| -pz).collect::<Vec<_>>());
+        ev.flip_z();
+        assert_eq!(ev, orig);
+    }
+
+    #[test]
+    fn rotate() {
+        let mut ev = event();
+        let orig = ev.total_momentum();
+        ev.rotate_z(1.);
+        ev.rotate([0., 0., 1.], -1.);
+        let total = ev.total_momentum();
+        assert!((0..4).all(|i| (total[i] - orig[i]).abs() < 1e-5));
+    }
+
+    #[test]
+    fn flip_i_event() {
+        let mut ev = Event {
+            part: Part::I,
+            user_weights: Vec::from_iter((0..18).map(f64::from)),
+            ..event()
+        };
+        ev.flip_z();
+        assert_eq!(ev.user_weights[..2], [0., 1.]);
+        assert_eq!(
+            ev.user_weights[2..10],
+            [10., 11., 12., 13., 14., 15., 16., 17.]
+        );
+        assert_eq!(ev.user_weights[10..], [2., 3., 4., 5., 6., 7., 8., 9.]);
+    }
+
+    #[test]
+    fn no_partonic_cms() {
+        let mut ev = Event { x2: 0., ..event() };
+        let orig = ev.clone();
+        assert_eq!(
+            ev.to_partonic_cms(10.),
+            Err(FrameError::NoPartonicCms([4., 0.]))
+        );
+        assert_eq!(ev, orig);
+    }
+
+    #[test]
+    #[should_panic]
+    fn superluminal_boost() {
+        event().boost([0., 0., 1.]);
+    }
+}
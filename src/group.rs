@@ -0,0 +1,190 @@
+use crate::Event;
+
+/// Iterator over groups of consecutive events with the same `id`
+///
+/// In NLO ntuples, a real-emission event and its subtraction
+/// counter-events share the same `id`. Such events are strongly
+/// correlated and have to be treated together, e.g. when filling
+/// histograms.
+///
+/// If reading an event fails, the error is returned and the events
+/// collected so far for the current group are discarded.
+#[derive(Clone, Debug)]
+pub struct Groups<I> {
+    events: I,
+    peeked: Option<Event>,
+}
+
+impl<I> Groups<I> {
+    pub fn new(events: I) -> Self {
+        Self {
+            events,
+            peeked: None,
+        }
+    }
+
+    pub fn into_inner(self) -> I {
+        self.events
+    }
+}
+
+impl<I, E> Iterator for Groups<I>
+where
+    I: Iterator<Item = Result<Event, E>>,
+{
+    type Item = Result<Vec<Event>, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = match self.peeked.take() {
+            Some(event) => event,
+            None => match self.events.next()? {
+                Ok(event) => event,
+                Err(err) => return Some(Err(err)),
+            },
+        };
+        let mut group = vec![first];
+        for event in self.events.by_ref() {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => return Some(Err(err)),
+            };
+            if event.id != group[0].id {
+                self.peeked = Some(event);
+                break;
+            }
+            group.push(event);
+        }
+        Some(Ok(group))
+    }
+}
+
+/// Weights accumulated from the fills of correlated events
+pub trait Accumulator: Default {
+    /// Position of a fill, e.g. the value of the observable
+    type Point: Copy;
+
+    /// Add a single weighted fill
+    fn accumulate(&mut self, weight: f64, point: Self::Point);
+
+    /// Add the fills from one group of correlated events
+    ///
+    /// The summed weight of the group enters the sum of squared
+    /// weights.
+    fn add_group(&mut self, group: &Self);
+}
+
+/// Accumulated weights in a histogram bin
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct BinSums {
+    /// Sum of weights
+    pub sumw: f64,
+    /// Sum of squared weights
+    ///
+    /// For correlated events this is the sum over the squares of the
+    /// summed weights of each group
+    pub sumw2: f64,
+    /// Number of filled events
+    pub entries: u64,
+}
+
+impl Accumulator for BinSums {
+    type Point = ();
+
+    fn accumulate(&mut self, weight: f64, _point: ()) {
+        self.sumw += weight;
+        self.entries += 1;
+    }
+
+    fn add_group(&mut self, group: &Self) {
+        self.sumw += group.sumw;
+        self.sumw2 += group.sumw * group.sumw;
+        self.entries += group.entries;
+    }
+}
+
+/// Fill the events in a group of correlated events into histogram bins
+///
+/// `bin` should return the index of the bin the event falls into, or
+/// `None` if it should not be filled. The weights of all events in
+/// the group that fall into the same bin are added before squaring,
+/// so that the statistical error in each bin is correct.
+pub fn fill_group<F>(group: &[Event], bins: &mut [BinSums], mut bin: F)
+where
+    F: FnMut(&Event) -> Option<usize>,
+{
+    let mut fills = Vec::from_iter(
+        group
+            .iter()
+            .filter_map(|event| bin(event).map(|bin| (bin, (), event.weight))),
+    );
+    add_fills(&mut fills, bins);
+}
+
+/// Add the fills from a group of correlated events to `bins`
+///
+/// Each fill consists of the bin index, the position, and the
+/// weight. Fills into the same bin are combined before they are
+/// added, see [Accumulator::add_group].
+pub fn add_fills<A: Accumulator>(
+    fills: &mut [(usize, A::Point, f64)],
+    bins: &mut [A],
+) {
+    fills.sort_by_key(|(bin, _, _)| *bin);
+    for fills in fills.chunk_by(|a, b| a.0 == b.0) {
+        let mut sum = A::default();
+        for (_, point, weight) in fills {
+            sum.accumulate(*weight, *point);
+        }
+        bins[fills[0].0].add_group(&sum);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::Infallible;
+
+    use super::*;
+
+    fn event(id: i32, weight: f64) -> Event {
+        Event {
+            id,
+            weight,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn groups() {
+        let events = [event(1, 1.), event(1, -1.), event(2, 1.), event(1, 1.)];
+        let groups: Vec<_> =
+            Groups::new(events.iter().cloned().map(Ok::<_, Infallible>))
+                .map(Result::unwrap)
+                .collect();
+        assert_eq!(
+            groups,
+            [&events[..2], &events[2..3], &events[3..]].map(|g| g.to_vec())
+        );
+    }
+
+    #[test]
+    fn correlated_fill() {
+        let group = [event(1, 2.), event(1, -1.), event(1, 3.)];
+        let mut bins = [BinSums::default(); 2];
+        fill_group(&group, &mut bins, |ev| Some((ev.weight > 0.) as usize));
+        assert_eq!(
+            bins,
+            [
+                BinSums {
+                    sumw: -1.,
+                    sumw2: 1.,
+                    entries: 1
+                },
+                BinSums {
+                    sumw: 5.,
+                    sumw2: 25.,
+                    entries: 2
+                }
+            ]
+        );
+    }
+}
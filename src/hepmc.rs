@@ -0,0 +1,211 @@
+//! Conversion between ntuple events and HepMC2 event records
+//!
+//! [to_hepmc] and [from_hepmc] stream events between an
+//! [EventSource] or [EventSink] and HepMC2 text data. With the
+//! `compression` feature, compressed input is detected and
+//! decompressed transparently, see
+//! [decompress](crate::compression::decompress). To write compressed
+//! output, pass a mutable reference to an
+//! [Encoder](crate::compression::Encoder) as target and
+//! [finish](crate::compression::Encoder::finish) it afterwards.
+use std::io::{self, BufRead, Write};
+
+use thiserror::Error;
+
+use crate::{EventSink, EventSource};
+
+/// Write all events from `source` in the HepMC2 format
+///
+/// Returns the number of converted events.
+pub fn to_hepmc<S, W>(
+    source: S,
+    out: W,
+) -> Result<usize, HepMCError<S::Error, io::Error>>
+where
+    S: EventSource,
+    W: Write,
+{
+    to_hepmc_with_progress(source, out, |_| {})
+}
+
+/// Write all events from `source` in the HepMC2 format
+///
+/// After each event, `progress` is called with the number of events
+/// converted so far. Returns the total number of converted events.
+pub fn to_hepmc_with_progress<S, W, F>(
+    mut source: S,
+    out: W,
+    mut progress: F,
+) -> Result<usize, HepMCError<S::Error, io::Error>>
+where
+    S: EventSource,
+    W: Write,
+    F: FnMut(usize),
+{
+    let mut writer = hepmc2::Writer::try_from(out).map_err(HepMCError::Io)?;
+    let mut nevents = 0;
+    while let Some(event) = source.read_event() {
+        let event = event.map_err(|source| HepMCError::Read {
+            event: nevents,
+            source,
+        })?;
+        writer
+            .write(&hepmc2::Event::from(&event))
+            .map_err(|source| HepMCError::Write {
+                event: nevents,
+                source,
+            })?;
+        nevents += 1;
+        progress(nevents);
+    }
+    writer.finish().map_err(HepMCError::Io)?;
+    Ok(nevents)
+}
+
+/// Read HepMC2 events from `input` and write them to `sink`
+///
+/// With the `compression` feature, `input` may be compressed with
+/// gzip or zstd. Returns the number of converted events.
+pub fn from_hepmc<R, K>(
+    input: R,
+    sink: K,
+) -> Result<usize, HepMCError<hepmc2::reader::LineParseError, K::Error>>
+where
+    R: BufRead,
+    K: EventSink,
+{
+    from_hepmc_with_progress(input, sink, |_| {})
+}
+
+/// Read HepMC2 events from `input` and write them to `sink`
+///
+/// With the `compression` feature, `input` may be compressed with
+/// gzip or zstd. After each event, `progress` is called with the
+/// number of events converted so far. Returns the total number of
+/// converted events.
+pub fn from_hepmc_with_progress<R, K, F>(
+    input: R,
+    mut sink: K,
+    mut progress: F,
+) -> Result<usize, HepMCError<hepmc2::reader::LineParseError, K::Error>>
+where
+    R: BufRead,
+    K: EventSink,
+    F: FnMut(usize),
+{
+    #[cfg(feature = "compression")]
+    let input =
+        crate::compression::decompress(input).map_err(HepMCError::Io)?;
+    let mut nevents = 0;
+    for event in hepmc2::Reader::new(input) {
+        let event = event.map_err(|source| HepMCError::Read {
+            event: nevents,
+            source,
+        })?;
+        sink.write_event(&(&event).into()).map_err(|source| {
+            HepMCError::Write {
+                event: nevents,
+                source,
+            }
+        })?;
+        nevents += 1;
+        progress(nevents);
+    }
+    Ok(nevents)
+}
+
+/// Error during HepMC2 conversion
+///
+/// `event` is the zero-based index of the event that could not be
+/// converted.
+#[derive(Debug, Error)]
+pub enum HepMCError<R, W> {
+    #[error("Failed to read event {event}")]
+    Read { event: usize, source: R },
+    #[error("Failed to write event {event}")]
+    Write { event: usize, source: W },
+    #[error("I/O error")]
+    Io(#[source] io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Event;
+
+    fn events() -> Vec<Event> {
+        vec![
+            Event {
+                id: 1,
+                nparticle: 1,
+                px: vec![1.],
+                py: vec![2.],
+                pz: vec![3.],
+                energy: vec![4.],
+                pdg_code: vec![21],
+                id1: 1,
+                id2: -1,
+                x1: 0.1,
+                x2: 0.2,
+                weight: 2.,
+                ..Default::default()
+            },
+            Event {
+                id: 2,
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn check_read(buf: &[u8], events: &[Event]) {
+        let mut read = Vec::new();
+        let nread = from_hepmc(buf, &mut read).unwrap();
+        assert_eq!(nread, events.len());
+        assert_eq!(read.len(), events.len());
+        for (read, event) in read.iter().zip(events) {
+            assert_eq!(read.id, event.id);
+            assert_eq!(read.nparticle, event.nparticle);
+            assert_eq!(read.pdg_code, event.pdg_code);
+            assert_eq!(read.weight, event.weight);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let events = events();
+        let mut buf = Vec::new();
+        let mut nprogress = 0;
+        let nwritten =
+            to_hepmc_with_progress(events.clone().into_iter(), &mut buf, |n| {
+                nprogress = n
+            })
+            .unwrap();
+        assert_eq!(nwritten, events.len());
+        assert_eq!(nprogress, events.len());
+        check_read(&buf, &events);
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn round_trip_compressed() {
+        use crate::compression::{compress, Compression};
+
+        let events = events();
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let mut buf = Vec::new();
+            let mut out = compress(&mut buf, compression).unwrap();
+            let nwritten =
+                to_hepmc(events.clone().into_iter(), &mut out).unwrap();
+            assert_eq!(nwritten, events.len());
+            out.finish().unwrap();
+            check_read(&buf, &events);
+        }
+    }
+
+    #[test]
+    fn parse_error() {
+        let input = b"HepMC::Version 2.06.09\nHepMC::IO_GenEvent-START_EVENT_LISTING\nE nonsense\n";
+        let err = from_hepmc(input.as_slice(), Vec::new()).unwrap_err();
+        assert!(matches!(err, HepMCError::Read { event: 0, .. }));
+    }
+}
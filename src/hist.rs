@@ -0,0 +1,716 @@
+//! Histograms with weighted fills
+//!
+//! Histograms are filled with groups of correlated events, i.e.
+//! consecutive events sharing the same `id` (see
+//! [Groups](crate::group::Groups)). Weights within a group are added
+//! before squaring, which gives the correct statistical errors for
+//! NLO real-emission events and their subtraction counter-events.
+//!
+//! Apart from the nominal `weight`, each histogram can carry
+//! additional weight streams for the `user_weights` variations.
+//! Histograms can be written in the YODA format used by Rivet, or as
+//! CSV.
+use std::io::{self, Write};
+
+use thiserror::Error;
+
+use crate::{
+    group::{add_fills, Accumulator},
+    Event,
+};
+
+/// Binning along one axis
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Axis {
+    edges: Vec<f64>,
+}
+
+impl Axis {
+    /// Axis with `nbins` bins of equal size between `low` and `high`
+    pub fn uniform(
+        nbins: usize,
+        low: f64,
+        high: f64,
+    ) -> Result<Self, AxisError> {
+        let width = (high - low) / nbins as f64;
+        let edges = Vec::from_iter((0..=nbins).map(|i| low + i as f64 * width));
+        Self::from_edges(edges)
+    }
+
+    /// Axis with the given bin edges
+    pub fn from_edges(edges: Vec<f64>) -> Result<Self, AxisError> {
+        if edges.len() < 2 {
+            return Err(AxisError::TooFewEdges);
+        }
+        if edges.iter().any(|e| !e.is_finite()) {
+            return Err(AxisError::NonFinite);
+        }
+        if edges.windows(2).any(|e| e[0] >= e[1]) {
+            return Err(AxisError::NotIncreasing);
+        }
+        Ok(Self { edges })
+    }
+
+    pub fn nbins(&self) -> usize {
+        self.edges.len() - 1
+    }
+
+    pub fn edges(&self) -> &[f64] {
+        &self.edges
+    }
+
+    /// Lower and upper edge of the `i`th bin
+    pub fn bin_range(&self, i: usize) -> (f64, f64) {
+        (self.edges[i], self.edges[i + 1])
+    }
+
+    /// Index of the bin containing `x`
+    pub fn index(&self, x: f64) -> BinIndex {
+        if x < self.edges[0] {
+            BinIndex::Underflow
+        } else if x >= *self.edges.last().unwrap() {
+            BinIndex::Overflow
+        } else {
+            let idx = self.edges.partition_point(|e| *e <= x);
+            BinIndex::Bin(idx - 1)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum BinIndex {
+    Underflow,
+    Bin(usize),
+    Overflow,
+}
+
+#[derive(Copy, Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum AxisError {
+    #[error("Need at least two bin edges")]
+    TooFewEdges,
+    #[error("Bin edges have to be finite")]
+    NonFinite,
+    #[error("Bin edges have to be strictly increasing")]
+    NotIncreasing,
+}
+
+/// Weight distribution along one dimension
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct Dbn1D {
+    pub sumw: f64,
+    /// Sum over squared weights, where the weights of correlated
+    /// events are added before squaring
+    pub sumw2: f64,
+    pub sumwx: f64,
+    pub sumwx2: f64,
+    pub entries: u64,
+}
+
+impl Accumulator for Dbn1D {
+    type Point = f64;
+
+    fn accumulate(&mut self, w: f64, x: f64) {
+        self.sumw += w;
+        self.sumwx += w * x;
+        self.sumwx2 += w * x * x;
+        self.entries += 1;
+    }
+
+    fn add_group(&mut self, group: &Self) {
+        self.sumw += group.sumw;
+        self.sumw2 += group.sumw * group.sumw;
+        self.sumwx += group.sumwx;
+        self.sumwx2 += group.sumwx2;
+        self.entries += group.entries;
+    }
+}
+
+impl Dbn1D {
+    fn scale(&mut self, factor: f64) {
+        self.sumw *= factor;
+        self.sumw2 *= factor * factor;
+        self.sumwx *= factor;
+        self.sumwx2 *= factor;
+    }
+
+    /// Statistical error of the sum of weights
+    pub fn err(&self) -> f64 {
+        self.sumw2.sqrt()
+    }
+}
+
+/// Weight distribution along two dimensions
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct Dbn2D {
+    pub sumw: f64,
+    /// Sum over squared weights, where the weights of correlated
+    /// events are added before squaring
+    pub sumw2: f64,
+    pub sumwx: f64,
+    pub sumwx2: f64,
+    pub sumwy: f64,
+    pub sumwy2: f64,
+    pub sumwxy: f64,
+    pub entries: u64,
+}
+
+impl Accumulator for Dbn2D {
+    type Point = (f64, f64);
+
+    fn accumulate(&mut self, w: f64, (x, y): (f64, f64)) {
+        self.sumw += w;
+        self.sumwx += w * x;
+        self.sumwx2 += w * x * x;
+        self.sumwy += w * y;
+        self.sumwy2 += w * y * y;
+        self.sumwxy += w * x * y;
+        self.entries += 1;
+    }
+
+    fn add_group(&mut self, group: &Self) {
+        self.sumw += group.sumw;
+        self.sumw2 += group.sumw * group.sumw;
+        self.sumwx += group.sumwx;
+        self.sumwx2 += group.sumwx2;
+        self.sumwy += group.sumwy;
+        self.sumwy2 += group.sumwy2;
+        self.sumwxy += group.sumwxy;
+        self.entries += group.entries;
+    }
+}
+
+impl Dbn2D {
+    fn scale(&mut self, factor: f64) {
+        self.sumw *= factor;
+        self.sumw2 *= factor * factor;
+        self.sumwx *= factor;
+        self.sumwx2 *= factor;
+        self.sumwy *= factor;
+        self.sumwy2 *= factor;
+        self.sumwxy *= factor;
+    }
+
+    /// Statistical error of the sum of weights
+    pub fn err(&self) -> f64 {
+        self.sumw2.sqrt()
+    }
+}
+
+// Weight of an event in the given stream
+//
+// Stream 0 is the nominal weight, stream `i` is the `i-1`th user
+// weight. Missing user weights count as zero.
+fn stream_weight(event: &Event, stream: usize) -> f64 {
+    match stream {
+        0 => event.weight,
+        n => event.user_weights.get(n - 1).copied().unwrap_or(0.),
+    }
+}
+
+// Path of the histogram for the given weight stream
+fn stream_path(path: &str, stream: usize) -> String {
+    match stream {
+        0 => path.to_owned(),
+        n => format!("{path}[user_weight_{}]", n - 1),
+    }
+}
+
+/// One-dimensional histogram
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Hist1D {
+    path: String,
+    title: String,
+    axis: Axis,
+    // the following are indexed by the weight stream
+    // bins are stored as underflow, regular bins, overflow
+    bins: Vec<Vec<Dbn1D>>,
+    total: Vec<Dbn1D>,
+}
+
+impl Hist1D {
+    /// Create a new histogram
+    ///
+    /// `path` is the YODA path, e.g. "/ANALYSIS/pt_j1"
+    pub fn new(path: impl Into<String>, axis: Axis) -> Self {
+        let nbins = axis.nbins() + 2;
+        Self {
+            path: path.into(),
+            title: String::new(),
+            axis,
+            bins: vec![vec![Dbn1D::default(); nbins]],
+            total: vec![Dbn1D::default()],
+        }
+    }
+
+    /// Also fill the given number of user weight variations
+    pub fn with_user_weights(mut self, n: usize) -> Self {
+        let nbins = self.axis.nbins() + 2;
+        self.bins.resize(n + 1, vec![Dbn1D::default(); nbins]);
+        self.total.resize(n + 1, Dbn1D::default());
+        self
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn axis(&self) -> &Axis {
+        &self.axis
+    }
+
+    /// Number of user weight variations
+    pub fn num_user_weights(&self) -> usize {
+        self.bins.len() - 1
+    }
+
+    /// Regular bins for the nominal weight
+    pub fn bins(&self) -> &[Dbn1D] {
+        self.variation_bins(0)
+    }
+
+    /// Regular bins for the given weight stream
+    ///
+    /// Stream 0 is the nominal weight, stream `i` is the `i-1`th user weight.
+    pub fn variation_bins(&self, stream: usize) -> &[Dbn1D] {
+        let bins = &self.bins[stream];
+        &bins[1..bins.len() - 1]
+    }
+
+    pub fn underflow(&self) -> &Dbn1D {
+        &self.bins[0][0]
+    }
+
+    pub fn overflow(&self) -> &Dbn1D {
+        self.bins[0].last().unwrap()
+    }
+
+    /// Distribution over all bins, including underflow and overflow
+    pub fn total(&self) -> &Dbn1D {
+        &self.total[0]
+    }
+
+    /// Fill a single event
+    ///
+    /// Nothing is filled if `obs` returns `None`.
+    pub fn fill<F>(&mut self, event: &Event, obs: F)
+    where
+        F: FnMut(&Event) -> Option<f64>,
+    {
+        self.fill_group(std::slice::from_ref(event), obs)
+    }
+
+    /// Fill a group of correlated events
+    ///
+    /// `obs` computes the observable for each event. Events for which
+    /// it returns `None` are not filled.
+    pub fn fill_group<F>(&mut self, group: &[Event], mut obs: F)
+    where
+        F: FnMut(&Event) -> Option<f64>,
+    {
+        let fills = Vec::from_iter(group.iter().filter_map(|ev| {
+            let x = obs(ev).filter(|x| !x.is_nan())?;
+            let idx = match self.axis.index(x) {
+                BinIndex::Underflow => 0,
+                BinIndex::Bin(n) => n + 1,
+                BinIndex::Overflow => self.axis.nbins() + 1,
+            };
+            Some((idx, x, ev))
+        }));
+        for (stream, bins) in self.bins.iter_mut().enumerate() {
+            let mut bin_fills = Vec::from_iter(
+                fills
+                    .iter()
+                    .map(|(idx, x, ev)| (*idx, *x, stream_weight(ev, stream))),
+            );
+            add_fills(&mut bin_fills, bins);
+            let mut total_fills = Vec::from_iter(
+                fills
+                    .iter()
+                    .map(|(_, x, ev)| (0, *x, stream_weight(ev, stream))),
+            );
+            add_fills(
+                &mut total_fills,
+                std::slice::from_mut(&mut self.total[stream]),
+            );
+        }
+    }
+
+    /// Multiply all weights by a constant factor
+    pub fn scale(&mut self, factor: f64) {
+        let bins = self.bins.iter_mut().flatten();
+        for dbn in bins.chain(self.total.iter_mut()) {
+            dbn.scale(factor);
+        }
+    }
+
+    /// Write the histogram in YODA format
+    ///
+    /// Each user weight variation is written as a separate histogram
+    /// with "[user_weight_<n>]" appended to the path.
+    pub fn write_yoda<W: Write>(&self, mut out: W) -> io::Result<()> {
+        for stream in 0..self.bins.len() {
+            let path = stream_path(&self.path, stream);
+            let bins = &self.bins[stream];
+            let total = &self.total[stream];
+            let mean = total.sumwx / total.sumw;
+            let area: f64 = bins.iter().map(|b| b.sumw).sum();
+            writeln!(out, "BEGIN YODA_HISTO1D_V2 {path}")?;
+            writeln!(out, "Path: {path}")?;
+            writeln!(out, "ScaledBy: 1")?;
+            writeln!(out, "Title: {}", self.title)?;
+            writeln!(out, "Type: Histo1D")?;
+            writeln!(out, "---")?;
+            writeln!(out, "# Mean: {mean:.6e}")?;
+            writeln!(out, "# Area: {area:.6e}")?;
+            writeln!(out, "# ID\tID\tsumw\tsumw2\tsumwx\tsumwx2\tnumEntries")?;
+            let rows = [
+                ("Total", total),
+                ("Underflow", &bins[0]),
+                ("Overflow", bins.last().unwrap()),
+            ];
+            for (name, dbn) in rows {
+                writeln!(out, "{name}\t{name}\t{}", format_dbn1d(dbn))?;
+            }
+            writeln!(
+                out,
+                "# xlow\txhigh\tsumw\tsumw2\tsumwx\tsumwx2\tnumEntries"
+            )?;
+            for (i, dbn) in bins[1..bins.len() - 1].iter().enumerate() {
+                let (low, high) = self.axis.bin_range(i);
+                writeln!(out, "{low:.6e}\t{high:.6e}\t{}", format_dbn1d(dbn))?;
+            }
+            writeln!(out, "END YODA_HISTO1D_V2")?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    /// Write the histogram as comma-separated values
+    ///
+    /// Each row corresponds to one bin. After the bin edges, there is
+    /// one column with the sum of weights and one with its
+    /// statistical error for each weight stream.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(out, "xlow,xhigh,sumw,err")?;
+        for n in 0..self.num_user_weights() {
+            write!(out, ",sumw_user_weight_{n},err_user_weight_{n}")?;
+        }
+        writeln!(out)?;
+        for i in 0..self.axis.nbins() {
+            let (low, high) = self.axis.bin_range(i);
+            write!(out, "{low:e},{high:e}")?;
+            for bins in &self.bins {
+                let dbn = &bins[i + 1];
+                write!(out, ",{:e},{:e}", dbn.sumw, dbn.err())?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+fn format_dbn1d(dbn: &Dbn1D) -> String {
+    format!(
+        "{:.6e}\t{:.6e}\t{:.6e}\t{:.6e}\t{:.6e}",
+        dbn.sumw, dbn.sumw2, dbn.sumwx, dbn.sumwx2, dbn.entries as f64
+    )
+}
+
+/// Two-dimensional histogram
+///
+/// Events outside the range of the axes only contribute to the
+/// total distribution.
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct Hist2D {
+    path: String,
+    title: String,
+    x_axis: Axis,
+    y_axis: Axis,
+    // the following are indexed by the weight stream
+    // bins are stored in row-major order with respect to x
+    bins: Vec<Vec<Dbn2D>>,
+    total: Vec<Dbn2D>,
+}
+
+impl Hist2D {
+    /// Create a new histogram
+    ///
+    /// `path` is the YODA path, e.g. "/ANALYSIS/pt_j1_vs_pt_j2"
+    pub fn new(path: impl Into<String>, x_axis: Axis, y_axis: Axis) -> Self {
+        let nbins = x_axis.nbins() * y_axis.nbins();
+        Self {
+            path: path.into(),
+            title: String::new(),
+            x_axis,
+            y_axis,
+            bins: vec![vec![Dbn2D::default(); nbins]],
+            total: vec![Dbn2D::default()],
+        }
+    }
+
+    /// Also fill the given number of user weight variations
+    pub fn with_user_weights(mut self, n: usize) -> Self {
+        let nbins = self.x_axis.nbins() * self.y_axis.nbins();
+        self.bins.resize(n + 1, vec![Dbn2D::default(); nbins]);
+        self.total.resize(n + 1, Dbn2D::default());
+        self
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn x_axis(&self) -> &Axis {
+        &self.x_axis
+    }
+
+    pub fn y_axis(&self) -> &Axis {
+        &self.y_axis
+    }
+
+    /// Number of user weight variations
+    pub fn num_user_weights(&self) -> usize {
+        self.bins.len() - 1
+    }
+
+    /// Bin for the nominal weight
+    pub fn bin(&self, ix: usize, iy: usize) -> &Dbn2D {
+        self.variation_bin(0, ix, iy)
+    }
+
+    /// Bin for the given weight stream
+    ///
+    /// Stream 0 is the nominal weight, stream `i` is the `i-1`th user weight.
+    pub fn variation_bin(&self, stream: usize, ix: usize, iy: usize) -> &Dbn2D {
+        &self.bins[stream][ix * self.y_axis.nbins() + iy]
+    }
+
+    /// Distribution over all events, including those outside the bins
+    pub fn total(&self) -> &Dbn2D {
+        &self.total[0]
+    }
+
+    /// Fill a single event
+    ///
+    /// Nothing is filled if `obs` returns `None`.
+    pub fn fill<F>(&mut self, event: &Event, obs: F)
+    where
+        F: FnMut(&Event) -> Option<(f64, f64)>,
+    {
+        self.fill_group(std::slice::from_ref(event), obs)
+    }
+
+    /// Fill a group of correlated events
+    ///
+    /// `obs` computes the observables for each event. Events for which
+    /// it returns `None` are not filled.
+    pub fn fill_group<F>(&mut self, group: &[Event], mut obs: F)
+    where
+        F: FnMut(&Event) -> Option<(f64, f64)>,
+    {
+        let ny = self.y_axis.nbins();
+        let fills = Vec::from_iter(group.iter().filter_map(|ev| {
+            let (x, y) = obs(ev).filter(|(x, y)| !x.is_nan() && !y.is_nan())?;
+            let idx = match (self.x_axis.index(x), self.y_axis.index(y)) {
+                (BinIndex::Bin(ix), BinIndex::Bin(iy)) => Some(ix * ny + iy),
+                _ => None,
+            };
+            Some((idx, (x, y), ev))
+        }));
+        for (stream, bins) in self.bins.iter_mut().enumerate() {
+            // events outside the bins only enter the total
+            let mut bin_fills =
+                Vec::from_iter(fills.iter().filter_map(|(idx, xy, ev)| {
+                    Some(((*idx)?, *xy, stream_weight(ev, stream)))
+                }));
+            add_fills(&mut bin_fills, bins);
+            let mut total_fills = Vec::from_iter(
+                fills
+                    .iter()
+                    .map(|(_, xy, ev)| (0, *xy, stream_weight(ev, stream))),
+            );
+            add_fills(
+                &mut total_fills,
+                std::slice::from_mut(&mut self.total[stream]),
+            );
+        }
+    }
+
+    /// Multiply all weights by a constant factor
+    pub fn scale(&mut self, factor: f64) {
+        let bins = self.bins.iter_mut().flatten();
+        for dbn in bins.chain(self.total.iter_mut()) {
+            dbn.scale(factor);
+        }
+    }
+
+    /// Write the histogram in YODA format
+    ///
+    /// Each user weight variation is written as a separate histogram
+    /// with "[user_weight_<n>]" appended to the path.
+    pub fn write_yoda<W: Write>(&self, mut out: W) -> io::Result<()> {
+        const COLUMNS: &str =
+            "sumw\tsumw2\tsumwx\tsumwx2\tsumwy\tsumwy2\tsumwxy\tnumEntries";
+        for stream in 0..self.bins.len() {
+            let path = stream_path(&self.path, stream);
+            let total = &self.total[stream];
+            let mean_x = total.sumwx / total.sumw;
+            let mean_y = total.sumwy / total.sumw;
+            let volume: f64 = self.bins[stream].iter().map(|b| b.sumw).sum();
+            writeln!(out, "BEGIN YODA_HISTO2D_V2 {path}")?;
+            writeln!(out, "Path: {path}")?;
+            writeln!(out, "ScaledBy: 1")?;
+            writeln!(out, "Title: {}", self.title)?;
+            writeln!(out, "Type: Histo2D")?;
+            writeln!(out, "---")?;
+            writeln!(out, "# Mean: ({mean_x:.6e}, {mean_y:.6e})")?;
+            writeln!(out, "# Volume: {volume:.6e}")?;
+            writeln!(out, "# ID\tID\t{COLUMNS}")?;
+            writeln!(out, "Total\tTotal\t{}", format_dbn2d(total))?;
+            writeln!(
+                out,
+                "# 2D outflow persistency not currently supported until API is stable"
+            )?;
+            writeln!(out, "# xlow\txhigh\tylow\tyhigh\t{COLUMNS}")?;
+            for (n, dbn) in self.bins[stream].iter().enumerate() {
+                let ((xlow, xhigh), (ylow, yhigh)) = self.bin_ranges(n);
+                writeln!(
+                    out,
+                    "{xlow:.6e}\t{xhigh:.6e}\t{ylow:.6e}\t{yhigh:.6e}\t{}",
+                    format_dbn2d(dbn)
+                )?;
+            }
+            writeln!(out, "END YODA_HISTO2D_V2")?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    // x and y ranges of the bin with the given internal index
+    fn bin_ranges(&self, n: usize) -> ((f64, f64), (f64, f64)) {
+        let ny = self.y_axis.nbins();
+        (self.x_axis.bin_range(n / ny), self.y_axis.bin_range(n % ny))
+    }
+
+    /// Write the histogram as comma-separated values
+    ///
+    /// Each row corresponds to one bin. After the bin edges, there is
+    /// one column with the sum of weights and one with its
+    /// statistical error for each weight stream.
+    pub fn write_csv<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(out, "xlow,xhigh,ylow,yhigh,sumw,err")?;
+        for n in 0..self.num_user_weights() {
+            write!(out, ",sumw_user_weight_{n},err_user_weight_{n}")?;
+        }
+        writeln!(out)?;
+        for ix in 0..self.x_axis.nbins() {
+            let (xlow, xhigh) = self.x_axis.bin_range(ix);
+            for iy in 0..self.y_axis.nbins() {
+                let (ylow, yhigh) = self.y_axis.bin_range(iy);
+                write!(out, "{xlow:e},{xhigh:e},{ylow:e},{yhigh:e}")?;
+                for stream in 0..self.bins.len() {
+                    let dbn = self.variation_bin(stream, ix, iy);
+                    write!(out, ",{:e},{:e}", dbn.sumw, dbn.err())?;
+                }
+                writeln!(out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn format_dbn2d(dbn: &Dbn2D) -> String {
+    format!(
+        "{:.6e}\t{:.6e}\t{:.6e}\t{:.6e}\t{:.6e}\t{:.6e}\t{:.6e}\t{:.6e}",
+        dbn.sumw,
+        dbn.sumw2,
+        dbn.sumwx,
+        dbn.sumwx2,
+        dbn.sumwy,
+        dbn.sumwy2,
+        dbn.sumwxy,
+        dbn.entries as f64
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: i32, weight: f64, x: f64) -> Event {
+        Event {
+            id,
+            weight,
+            user_weights: vec![2. * weight],
+            alphas: x,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn hist1d() {
+        let axis = Axis::uniform(2, 0., 2.).unwrap();
+        let mut hist = Hist1D::new("/TEST/h", axis).with_user_weights(1);
+        let group = [event(1, 2., 0.5), event(1, -1., 0.7), event(1, 1., 1.5)];
+        hist.fill_group(&group, |ev| Some(ev.alphas));
+        hist.fill(&event(2, 1., 3.), |ev| Some(ev.alphas));
+
+        let bins = hist.bins();
+        assert_eq!(bins[0].sumw, 1.);
+        assert_eq!(bins[0].sumw2, 1.);
+        assert_eq!(bins[0].entries, 2);
+        assert_eq!(bins[1].sumw, 1.);
+        assert_eq!(hist.overflow().sumw, 1.);
+        assert_eq!(hist.total().sumw, 3.);
+        assert_eq!(hist.total().sumw2, 5.);
+        assert_eq!(hist.variation_bins(1)[0].sumw2, 4.);
+
+        let mut yoda = Vec::new();
+        hist.write_yoda(&mut yoda).unwrap();
+        let yoda = String::from_utf8(yoda).unwrap();
+        assert!(yoda.starts_with("BEGIN YODA_HISTO1D_V2 /TEST/h\n"));
+        assert!(yoda.contains("BEGIN YODA_HISTO1D_V2 /TEST/h[user_weight_0]"));
+
+        let mut csv = Vec::new();
+        hist.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().count(), 3);
+    }
+
+    #[test]
+    fn hist2d() {
+        let axis = Axis::from_edges(vec![0., 1., 3.]).unwrap();
+        let mut hist = Hist2D::new("/TEST/h2", axis.clone(), axis);
+        let group = [event(1, 2., 0.5), event(1, -1., 0.7), event(1, 1., 1.5)];
+        hist.fill_group(&group, |ev| Some((ev.alphas, 2. * ev.alphas)));
+        assert_eq!(hist.bin(0, 0).sumw, 0.);
+        assert_eq!(hist.bin(0, 1).sumw, 1.);
+        assert_eq!(hist.bin(0, 1).entries, 2);
+        assert_eq!(hist.bin(1, 1).sumw, 0.);
+        assert_eq!(hist.total().sumw, 2.);
+        assert_eq!(hist.total().sumw2, 4.);
+
+        let mut yoda = Vec::new();
+        hist.write_yoda(&mut yoda).unwrap();
+        let yoda = String::from_utf8(yoda).unwrap();
+        assert!(yoda.contains("# Volume: 1.000000e0\n"));
+    }
+}
@@ -0,0 +1,247 @@
+use crate::{Event, FourMomentum};
+
+/// Sequential recombination jet algorithm
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum JetAlgorithm {
+    AntiKt,
+    Kt,
+    CambridgeAachen,
+}
+
+impl JetAlgorithm {
+    // exponent of the transverse momentum in the distance measure
+    fn pt_power(self) -> i32 {
+        match self {
+            JetAlgorithm::AntiKt => -1,
+            JetAlgorithm::Kt => 1,
+            JetAlgorithm::CambridgeAachen => 0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct JetDefinition {
+    pub algorithm: JetAlgorithm,
+    /// Jet radius
+    pub radius: f64,
+}
+
+/// Whether the particle with the given PDG id is a QCD parton
+///
+/// Partons are the gluon and all quarks except for the top quark.
+pub fn is_parton(pdg: i32) -> bool {
+    pdg == 21 || (1..=5).contains(&pdg.abs())
+}
+
+/// Cluster momenta into inclusive jets
+///
+/// Momenta are combined with the E-scheme and distances are measured
+/// in the rapidity-azimuth plane. The jets are returned in order of
+/// decreasing transverse momentum.
+pub fn cluster<I>(momenta: I, def: &JetDefinition) -> Vec<FourMomentum>
+where
+    I: IntoIterator<Item = FourMomentum>,
+{
+    let power = def.algorithm.pt_power();
+    let r2 = def.radius * def.radius;
+    let beam_dist = |p: &FourMomentum| p.pt2().powi(power);
+
+    let mut pseudojets = Vec::from_iter(momenta);
+    let mut jets = Vec::with_capacity(pseudojets.len());
+    while !pseudojets.is_empty() {
+        // find the smallest distance, `j == i` stands for the
+        // distance to the beam
+        let mut min = (f64::INFINITY, 0, 0);
+        for (i, pi) in pseudojets.iter().enumerate() {
+            let di = beam_dist(pi);
+            if di < min.0 {
+                min = (di, i, i);
+            }
+            for (j, pj) in pseudojets.iter().enumerate().skip(i + 1) {
+                let dij = di.min(beam_dist(pj)) * pi.delta_r(pj).powi(2) / r2;
+                if dij < min.0 {
+                    min = (dij, i, j);
+                }
+            }
+        }
+        let (_, i, j) = min;
+        if i == j {
+            jets.push(pseudojets.swap_remove(i));
+        } else {
+            let pj = pseudojets.swap_remove(j);
+            pseudojets[i] += pj;
+        }
+    }
+    jets.sort_by(|a, b| b.pt2().total_cmp(&a.pt2()));
+    jets
+}
+
+impl Event {
+    /// Cluster the outgoing partons into inclusive jets
+    ///
+    /// See [cluster] for details.
+    pub fn jets(&self, def: &JetDefinition) -> Vec<FourMomentum> {
+        let partons =
+            self.particles().filter(|p| is_parton(p.pdg)).map(|p| p.p);
+        cluster(partons, def)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn massless(pt: f64, y: f64, phi: f64) -> FourMomentum {
+        FourMomentum::new(
+            pt * y.cosh(),
+            pt * phi.cos(),
+            pt * phi.sin(),
+            pt * y.sinh(),
+        )
+    }
+
+    fn assert_jets_eq(jets: &[FourMomentum], expected: &[FourMomentum]) {
+        assert_eq!(jets.len(), expected.len());
+        for (jet, expected) in jets.iter().zip(expected) {
+            for i in 0..4 {
+                assert!((jet[i] - expected[i]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn clustering() {
+        use JetAlgorithm::*;
+
+        // a hard parton with a soft parton inside the jet radius and
+        // a soft parton close to the first soft parton
+        let hard = massless(100., 0.5, 0.);
+        let soft1 = massless(1., 0.5, 0.9);
+        let soft2 = massless(1., 0.5, 1.6);
+        let partons = [hard, soft1, soft2];
+
+        // anti-kt clusters soft particles around the hard one
+        let def = JetDefinition {
+            algorithm: AntiKt,
+            radius: 1.,
+        };
+        let jets = cluster(partons, &def);
+        assert_jets_eq(&jets, &[hard + soft1, soft2]);
+
+        // kt and C/A cluster the soft particles first
+        for algorithm in [Kt, CambridgeAachen] {
+            let def = JetDefinition {
+                algorithm,
+                radius: 1.,
+            };
+            let jets = cluster(partons, &def);
+            assert_jets_eq(&jets, &[hard, soft1 + soft2]);
+        }
+
+        // with a smaller radius nothing is clustered
+        for algorithm in [AntiKt, Kt, CambridgeAachen] {
+            let def = JetDefinition {
+                algorithm,
+                radius: 0.4,
+            };
+            let jets = cluster(partons, &def);
+            assert_jets_eq(&jets, &[hard, soft1, soft2]);
+        }
+    }
+
+    #[test]
+    #[ignore = "run test_data/jets/make_reference.py with FastJet first"]
+    fn reference() {
+        use std::{fs::read_to_string, path::PathBuf};
+        use JetAlgorithm::*;
+
+        let mut data_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        data_path.push("test_data");
+        data_path.push("jets");
+        let event = read_to_string(data_path.join("event.dat")).unwrap();
+        let reference =
+            read_to_string(data_path.join("reference.dat")).unwrap();
+        assert!(reference.starts_with("# jets clustered with FastJet"));
+        let parse = |line: &str| -> Vec<f64> {
+            line.split_whitespace()
+                .map(|x| x.parse().unwrap())
+                .collect()
+        };
+        let partons = Vec::from_iter(event.lines().map(|line| {
+            let [px, py, pz, e] = parse(line)[..] else {
+                panic!("Bad line in event: {line}")
+            };
+            FourMomentum::new(e, px, py, pz)
+        }));
+
+        let mut expected: Vec<(JetDefinition, Vec<FourMomentum>)> = Vec::new();
+        for line in reference.lines().filter(|l| !l.starts_with('#')) {
+            let (algorithm, rest) = line.split_once(' ').unwrap();
+            let algorithm = match algorithm {
+                "antikt" => AntiKt,
+                "kt" => Kt,
+                "cambridge" => CambridgeAachen,
+                _ => panic!("Unknown algorithm {algorithm}"),
+            };
+            let [radius, px, py, pz, e] = parse(rest)[..] else {
+                panic!("Bad line in reference: {line}")
+            };
+            let def = JetDefinition { algorithm, radius };
+            let jet = FourMomentum::new(e, px, py, pz);
+            match expected.last_mut() {
+                Some((last, jets)) if *last == def => jets.push(jet),
+                _ => expected.push((def, vec![jet])),
+            }
+        }
+        assert_eq!(expected.len(), 6);
+
+        for (def, expected) in expected {
+            let jets = cluster(partons.iter().copied(), &def);
+            assert_eq!(jets.len(), expected.len(), "{def:?}");
+            for (jet, expected) in jets.iter().zip(&expected) {
+                for i in 0..4 {
+                    let diff = (jet[i] - expected[i]).abs();
+                    assert!(diff < 1e-9 * expected.e(), "{def:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fastjet_manual_example() {
+        // example from the FastJet user manual, which finds one jet
+        // with the first two particles and one with the third
+        let particles = [
+            FourMomentum::new(100., 99., 0.1, 0.),
+            FourMomentum::new(5., 4., -0.1, 0.),
+            FourMomentum::new(99., -99., 0., 0.),
+        ];
+        let def = JetDefinition {
+            algorithm: JetAlgorithm::AntiKt,
+            radius: 0.7,
+        };
+        let jets = cluster(particles, &def);
+        assert_jets_eq(&jets, &[particles[0] + particles[1], particles[2]]);
+        assert!((jets[0].pt() - 103.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn event_jets() {
+        let event = Event {
+            nparticle: 3,
+            px: vec![50., -30., -20.],
+            py: vec![0., 1., -1.],
+            pz: vec![0., 0., 0.],
+            energy: vec![50., 30.0167, 20.025],
+            pdg_code: vec![11, 21, 1],
+            ..Default::default()
+        };
+        let def = JetDefinition {
+            algorithm: JetAlgorithm::AntiKt,
+            radius: 0.4,
+        };
+        let jets = event.jets(&def);
+        assert_eq!(jets.len(), 1);
+        assert_jets_eq(&jets, &[event.momentum(1) + event.momentum(2)]);
+    }
+}
@@ -0,0 +1,154 @@
+//! Events in the JSON lines format
+//!
+//! Each line contains one event as a JSON object with the same field
+//! names as [Event].
+use std::io::{self, BufRead, Write};
+
+use thiserror::Error;
+
+use crate::{Event, EventSink, EventSource};
+
+/// Reader for events in the JSON lines format
+///
+/// Empty lines are skipped.
+#[derive(Debug)]
+pub struct JsonLinesReader<R> {
+    reader: R,
+    line: String,
+    line_nr: usize,
+}
+
+impl<R> JsonLinesReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            line_nr: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> Iterator for JsonLinesReader<R> {
+    type Item = Result<Event, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_nr += 1,
+                Err(err) => return Some(Err(err.into())),
+            }
+            let line = self.line.trim();
+            if !line.is_empty() {
+                let event = serde_json::from_str(line).map_err(|source| {
+                    JsonError::Parse {
+                        line: self.line_nr,
+                        source,
+                    }
+                });
+                return Some(event);
+            }
+        }
+    }
+}
+
+impl<R: BufRead> EventSource for JsonLinesReader<R> {
+    type Error = JsonError;
+
+    fn read_event(&mut self) -> Option<Result<Event, Self::Error>> {
+        self.next()
+    }
+}
+
+/// Writer for events in the JSON lines format
+#[derive(Debug)]
+pub struct JsonLinesWriter<W> {
+    writer: W,
+}
+
+impl<W> JsonLinesWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn write(&mut self, event: &Event) -> Result<(), JsonError> {
+        serde_json::to_writer(&mut self.writer, event)
+            .map_err(JsonError::Serialise)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), JsonError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write> EventSink for JsonLinesWriter<W> {
+    type Error = JsonError;
+
+    fn write_event(&mut self, event: &Event) -> Result<(), Self::Error> {
+        self.write(event)
+    }
+
+    fn finish(mut self) -> Result<(), Self::Error> {
+        self.flush()
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JsonError {
+    #[error("I/O error")]
+    Io(#[from] io::Error),
+    #[error("Failed to parse event in line {line}")]
+    Parse {
+        line: usize,
+        source: serde_json::Error,
+    },
+    #[error("Failed to serialise event")]
+    Serialise(#[source] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Part;
+
+    #[test]
+    fn round_trip() {
+        let events = vec![
+            Event {
+                id: 1,
+                nparticle: 1,
+                px: vec![1.],
+                py: vec![2.],
+                pz: vec![3.],
+                energy: vec![4.],
+                pdg_code: vec![21],
+                user_weights: vec![0.5, -0.5],
+                part: Part::R,
+                ..Default::default()
+            },
+            Event::default(),
+        ];
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        for event in &events {
+            writer.write(event).unwrap();
+        }
+        let buf = writer.into_inner();
+        let reader = JsonLinesReader::new(buf.as_slice());
+        let read: Vec<_> = reader.map(Result::unwrap).collect();
+        assert_eq!(read, events);
+    }
+}
@@ -1,12 +1,38 @@
 mod bindings;
+#[cfg(feature = "compression")]
+pub mod compression;
 #[cfg(feature = "hepmc2")]
 pub mod conv;
+pub mod error;
 pub mod event;
+pub mod filter;
+pub mod frame;
+pub mod group;
+#[cfg(feature = "hepmc2")]
+pub mod hepmc;
+pub mod hist;
+pub mod jets;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod normalise;
+pub mod particle;
 pub mod reader;
+pub mod resample;
+pub mod run_info;
+pub mod split;
+pub mod storage;
+#[cfg(feature = "unweight")]
+pub mod unweight;
+pub mod validate;
 pub mod writer;
+pub mod xsection;
 
+pub use crate::error::{Error, Result};
 pub use crate::event::Event;
+pub use crate::particle::{FourMomentum, Particle};
 pub use crate::reader::Reader;
+pub use crate::run_info::RunInfo;
+pub use crate::storage::{EventSink, EventSource};
 pub use crate::writer::Writer;
 
 include!(concat!(env!("OUT_DIR"), "/flags.rs"));
@@ -16,23 +42,30 @@ pub use get_root_flags::get_root_flags;
 #[cfg(test)]
 mod tests {
     use reader::Reader;
-    use std::{fs::read_dir, path::PathBuf};
+    use std::{fs::read_dir, path::PathBuf, result::Result};
     use tempfile::NamedTempFile;
 
     use super::*;
 
-    #[test]
-    fn test() {
+    // ROOT files in the `test_data` directory
+    fn root_files() -> Vec<PathBuf> {
         let mut data_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         data_path.push("test_data");
+        Vec::from_iter(
+            read_dir(data_path)
+                .unwrap()
+                .map(|entry| entry.unwrap().path())
+                .filter(|path| path.extension() == Some("root".as_ref())),
+        )
+    }
 
-        for root_file in read_dir(data_path).unwrap() {
-            let root_file = root_file.unwrap();
-
+    #[test]
+    fn test() {
+        for root_file in root_files() {
             let tmp1 = NamedTempFile::new().unwrap();
             let tmp2 = NamedTempFile::new().unwrap();
 
-            let reader = Reader::new(root_file.path()).unwrap();
+            let reader = Reader::new(&root_file).unwrap();
             let mut nevents = reader.size_hint().0 as i64;
             {
                 let mut writer = Writer::new(tmp1.path(), "").unwrap();
@@ -60,4 +93,248 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn in_memory() {
+        for root_file in root_files() {
+            let events: Vec<_> = Reader::new(&root_file)
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+
+            let bytes = std::fs::read(&root_file).unwrap();
+            let reader = Reader::from_bytes(&bytes).unwrap();
+            assert_eq!(Vec::from_iter(reader.map(Result::unwrap)), events);
+
+            let tmp = NamedTempFile::new().unwrap();
+            let mut writer =
+                Writer::to_writer(tmp.reopen().unwrap(), "").unwrap();
+            for event in &events {
+                writer.write(event).unwrap();
+            }
+            writer.finish().unwrap();
+
+            let reader = Reader::from_reader(tmp.reopen().unwrap()).unwrap();
+            assert_eq!(Vec::from_iter(reader.map(Result::unwrap)), events);
+
+            let mut writer = Writer::in_memory("").unwrap();
+            for event in &events {
+                writer.write(event).unwrap();
+            }
+            let buf = writer.into_bytes().unwrap();
+            let reader = Reader::from_bytes(&buf).unwrap();
+            assert_eq!(Vec::from_iter(reader.map(Result::unwrap)), events);
+        }
+    }
+
+    #[test]
+    fn run_info() {
+        let info = RunInfo {
+            beam_energy: Some([6500., 6500.]),
+            beam_id: Some([2212, 2212]),
+            generator: Some("BlackHat+Sherpa".to_owned()),
+            generator_version: Some("1.0".to_owned()),
+            pdf_set: Some("CT18NLO".to_owned()),
+            process: Some("p p -> W+ 2j".to_owned()),
+            ntrials: Some(12345),
+        };
+        let tmp = NamedTempFile::new().unwrap();
+        let mut writer = Writer::new(tmp.path(), "").unwrap();
+        writer.set_run_info(&RunInfo::default()).unwrap();
+        assert_eq!(
+            writer.set_run_info(&RunInfo {
+                process: Some("nul\0byte".to_owned()),
+                ..Default::default()
+            }),
+            Err(run_info::RunInfoError::NulByte(
+                "process",
+                "nul\0byte".to_owned()
+            ))
+        );
+        writer.set_run_info(&info).unwrap();
+        writer.write(&Event::default()).unwrap();
+        writer.finish().unwrap();
+
+        let reader = Reader::new(tmp.path()).unwrap();
+        assert_eq!(reader.run_info().unwrap(), info);
+        assert_eq!(reader.count(), 1);
+
+        for root_file in root_files() {
+            let reader = Reader::new(root_file).unwrap();
+            assert_eq!(reader.run_info().unwrap(), RunInfo::default());
+        }
+    }
+
+    #[test]
+    fn tree_info() {
+        let tmp = NamedTempFile::new().unwrap();
+        Writer::new(tmp.path(), "my title").unwrap();
+
+        let reader = Reader::new(tmp.path()).unwrap();
+        assert_eq!(reader.title(), "my title");
+        assert_eq!(reader.tree_name(), "BHSntuples");
+        assert!(!reader.is_legacy());
+        let leaves = reader.leaves().unwrap();
+        assert_eq!(leaves.len(), 24);
+        let px = leaves.iter().find(|l| l.name == "px").unwrap();
+        assert_eq!(px.branch, "px");
+        assert_eq!(px.leaf_type, "Float_t");
+        assert_eq!(px.length.as_deref(), Some("nparticle"));
+        let me_wgt = leaves.iter().find(|l| l.branch == "me_wgt").unwrap();
+        assert_eq!(me_wgt.name, "me_wtg");
+        assert_eq!(me_wgt.length, None);
+    }
+
+    #[test]
+    fn legacy_tree_info() {
+        let mut data_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        data_path.push("test_data");
+
+        // legacy.root is sample.root with the tree renamed to `t3`
+        let reader = Reader::new(data_path.join("legacy.root")).unwrap();
+        assert_eq!(reader.tree_name(), "t3");
+        assert!(reader.is_legacy());
+        let leaves = reader.leaves().unwrap();
+        assert_eq!(leaves.len(), 24);
+        let events = Vec::from_iter(reader.map(Result::unwrap));
+
+        let reader = Reader::new(data_path.join("sample.root")).unwrap();
+        assert!(!reader.is_legacy());
+        assert_eq!(Vec::from_iter(reader.map(Result::unwrap)), events);
+    }
+
+    #[test]
+    fn split_files() {
+        use crate::split::{split, Distribution, SplitOptions, SplitSize};
+
+        let tmp_dir = tempfile::tempdir().unwrap();
+        for root_file in root_files() {
+            let events: Vec<_> = Reader::new(&root_file)
+                .unwrap()
+                .map(Result::unwrap)
+                .collect();
+
+            for distribution in
+                [Distribution::Contiguous, Distribution::RoundRobin]
+            {
+                let opts = SplitOptions {
+                    distribution,
+                    keep_groups: true,
+                    ..SplitOptions::new(SplitSize::Parts(3))
+                };
+                let prefix = tmp_dir.path().join("part");
+                let parts = split(&root_file, prefix, &opts).unwrap();
+                assert_eq!(parts.len(), 3);
+
+                let parts = Vec::from_iter(parts.into_iter().map(|part| {
+                    Vec::from_iter(
+                        Reader::new(part).unwrap().map(Result::unwrap),
+                    )
+                }));
+                let nevents: usize = parts.iter().map(|p| p.len()).sum();
+                assert_eq!(nevents, events.len());
+                if distribution == Distribution::Contiguous {
+                    assert_eq!(parts.concat(), events);
+                    // no group may be split across parts
+                    let nonempty = parts.iter().filter(|p| !p.is_empty());
+                    for (part, next) in nonempty.clone().zip(nonempty.skip(1)) {
+                        assert_ne!(part.last().unwrap().id, next[0].id);
+                    }
+                }
+            }
+        }
+    }
+
+    // Write events with ids 0..5, where entries 1 and 2 have an
+    // invalid event type
+    fn write_bad_entries(path: &std::path::Path) {
+        let mut writer = Writer::new(path, "").unwrap();
+        for id in 0..5 {
+            let event = Event {
+                id,
+                ..Default::default()
+            };
+            if id == 1 || id == 2 {
+                let momenta: [f32; 0] = [];
+                let weights: [f64; 0] = [];
+                let pdg_codes: [i32; 0] = [];
+                let event = bindings::NTupleEvent {
+                    id,
+                    nparticle: 0,
+                    px: momenta.as_ptr(),
+                    py: momenta.as_ptr(),
+                    pz: momenta.as_ptr(),
+                    energy: momenta.as_ptr(),
+                    alphas: 0.,
+                    kf: pdg_codes.as_ptr(),
+                    weight: 0.,
+                    weight2: 0.,
+                    me_wgt: 0.,
+                    me_wgt2: 0.,
+                    x1: 0.,
+                    x2: 0.,
+                    x1p: 0.,
+                    x2p: 0.,
+                    id1: 0,
+                    id2: 0,
+                    fac_scale: 0.,
+                    ren_scale: 0.,
+                    nuwgt: 0,
+                    usr_wgts: weights.as_ptr(),
+                    part: b'X',
+                    alphas_power: 0,
+                };
+                writer.write_ntuple_event(&event).unwrap();
+            } else {
+                writer.write(&event).unwrap();
+            }
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn on_error() {
+        use reader::{OnError, ReadErrorKind, ReaderOptions};
+
+        let tmp = NamedTempFile::new().unwrap();
+        write_bad_entries(tmp.path());
+        let read = |on_error| {
+            let opts = ReaderOptions::new().on_error(on_error);
+            let mut reader = opts.open(tmp.path()).unwrap();
+            assert_eq!(reader.remaining_entries(), 5);
+            let res = Vec::from_iter(reader.by_ref().map(|ev| match ev {
+                Ok(ev) => Ok(ev.id),
+                Err(err) => Err(err.entry),
+            }));
+            (reader, res)
+        };
+
+        let (reader, res) = read(OnError::Continue);
+        assert_eq!(res, [Ok(0), Err(1), Err(2), Ok(3), Ok(4)]);
+        assert_eq!(reader.nlost(), 0);
+
+        let (_, res) = read(OnError::Stop);
+        assert_eq!(res, [Ok(0), Err(1)]);
+
+        let (reader, res) = read(OnError::Skip);
+        assert_eq!(res, [Ok(0), Ok(3), Ok(4)]);
+        assert_eq!(reader.lost_entries().len(), 1);
+        assert_eq!(reader.lost_entries()[0], 1..3);
+        assert!(reader.errors().is_empty());
+
+        let (reader, res) = read(OnError::Collect);
+        assert_eq!(res, [Ok(0), Ok(3), Ok(4)]);
+        assert_eq!(reader.lost_entries().len(), 1);
+        assert_eq!(reader.lost_entries()[0], 1..3);
+        let errors = Vec::from_iter(reader.errors().iter().map(|err| {
+            assert_eq!(err.kind, ReadErrorKind::BadPart(b'X'));
+            err.entry
+        }));
+        assert_eq!(errors, [1, 2]);
+
+        let opts = ReaderOptions::new().on_error(OnError::Skip);
+        let reader = opts.open(tmp.path()).unwrap();
+        assert_eq!(reader.size_hint(), (0, Some(5)));
+        assert_eq!(Reader::new(tmp.path()).unwrap().size_hint(), (5, Some(5)));
+    }
 }
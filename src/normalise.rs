@@ -0,0 +1,254 @@
+//! Normalisation of event weights
+//!
+//! All weights of an event, i.e. `weight`, `weight2`, `me_weight`,
+//! `me_weight2`, and `user_weights`, are rescaled by the same factor.
+//!
+//! The number of trials is recovered from the event ids. Within the
+//! output of one job, ids increase monotonically. When samples from
+//! several jobs are merged, the ids restart, so a decreasing id marks
+//! the start of a new job. Each job contributes `last_id - first_id + 1`
+//! trials.
+use crate::Event;
+
+impl Event {
+    /// Multiply all weights by `factor`
+    pub fn scale_weights(&mut self, factor: f64) {
+        self.weight *= factor;
+        self.weight2 *= factor;
+        self.me_weight *= factor;
+        self.me_weight2 *= factor;
+        for w in &mut self.user_weights {
+            *w *= factor;
+        }
+    }
+}
+
+/// Range of ids in the output of a single job
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct JobTrials {
+    pub first_id: i32,
+    pub last_id: i32,
+}
+
+impl JobTrials {
+    /// Number of trials in the job
+    pub fn ntrials(&self) -> u64 {
+        (i64::from(self.last_id) - i64::from(self.first_id) + 1) as u64
+    }
+}
+
+/// Trials recovered from the event ids of a (merged) sample
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Trials {
+    jobs: Vec<JobTrials>,
+}
+
+impl Trials {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recover the trials from a sequence of event ids
+    pub fn from_ids<I: IntoIterator<Item = i32>>(ids: I) -> Self {
+        let mut trials = Self::new();
+        for id in ids {
+            trials.add(id);
+        }
+        trials
+    }
+
+    /// Add the id of the next event
+    pub fn add(&mut self, id: i32) {
+        match self.jobs.last_mut() {
+            Some(job) if id >= job.last_id => job.last_id = id,
+            _ => self.jobs.push(JobTrials {
+                first_id: id,
+                last_id: id,
+            }),
+        }
+    }
+
+    /// Trials for each job
+    pub fn jobs(&self) -> &[JobTrials] {
+        &self.jobs
+    }
+
+    /// Total number of trials
+    pub fn ntrials(&self) -> u64 {
+        self.jobs.iter().map(|j| j.ntrials()).sum()
+    }
+}
+
+/// Rescale the weights of a stream of events
+///
+/// ```no_run
+/// use ntuple::{normalise::{Normaliser, Trials}, Reader, Writer};
+///
+/// // first pass: count trials
+/// let ids = Reader::new("in.root")?.map(|ev| ev.map(|ev| ev.id));
+/// let trials = Trials::from_ids(ids.collect::<Result<Vec<_>, _>>()?);
+///
+/// // second pass: merge jobs that were normalised individually
+/// let mut normaliser = Normaliser::per_job(&trials);
+/// let mut writer = Writer::new("out.root", "")?;
+/// for event in Reader::new("in.root")? {
+///     let mut event = event?;
+///     normaliser.normalise(&mut event);
+///     writer.write(&event)?;
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Normaliser {
+    factor: f64,
+    // per-job factors and the job of the last event
+    job_factors: Vec<f64>,
+    job: Trials,
+}
+
+impl Normaliser {
+    /// Scale all weights by `factor`
+    pub fn new(factor: f64) -> Self {
+        Self {
+            factor,
+            job_factors: Vec::new(),
+            job: Trials::new(),
+        }
+    }
+
+    /// Rescale from a cross section `current` to `target`
+    ///
+    /// Returns `None` if `current` is zero.
+    pub fn to_xsection(current: f64, target: f64) -> Option<Self> {
+        (current != 0.).then(|| Self::new(target / current))
+    }
+
+    /// Divide by the total number of trials
+    ///
+    /// Use this if the weights are not normalised, i.e. the sum of
+    /// weights in each job is the cross section times the number of
+    /// trials in the job.
+    ///
+    /// Returns `None` if there are no trials.
+    pub fn per_trial(trials: &Trials) -> Option<Self> {
+        match trials.ntrials() {
+            0 => None,
+            ntrials => Some(Self::new(1. / ntrials as f64)),
+        }
+    }
+
+    /// Combine jobs that are normalised individually
+    ///
+    /// Use this if the sum of weights in each job is the cross
+    /// section. The events of each job are weighted by the fraction
+    /// of trials in that job, so that the sum of weights in the
+    /// merged sample is again the cross section.
+    ///
+    /// The events have to be passed to [normalise](Self::normalise)
+    /// in the same order as the ids used to construct `trials`.
+    pub fn per_job(trials: &Trials) -> Self {
+        let ntrials = trials.ntrials() as f64;
+        let job_factors = trials
+            .jobs()
+            .iter()
+            .map(|j| j.ntrials() as f64 / ntrials)
+            .collect();
+        Self {
+            factor: 1.,
+            job_factors,
+            job: Trials::new(),
+        }
+    }
+
+    /// Additionally multiply all weights by `factor`
+    pub fn with_factor(mut self, factor: f64) -> Self {
+        self.factor *= factor;
+        self
+    }
+
+    /// Rescale the weights of the next event
+    pub fn normalise(&mut self, event: &mut Event) {
+        let mut factor = self.factor;
+        if !self.job_factors.is_empty() {
+            self.job.add(event.id);
+            let job = self.job.jobs().len() - 1;
+            // more jobs than expected means the events don't match
+            // the trials, so fall back to the last job
+            let idx = job.min(self.job_factors.len() - 1);
+            factor *= self.job_factors[idx];
+        }
+        event.scale_weights(factor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: i32, weight: f64) -> Event {
+        Event {
+            id,
+            weight,
+            weight2: weight,
+            me_weight: 2. * weight,
+            me_weight2: 2. * weight,
+            user_weights: vec![weight, -weight],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn trials() {
+        let trials = Trials::from_ids([1, 3, 3, 10, 2, 5, 1]);
+        assert_eq!(
+            trials.jobs(),
+            [
+                JobTrials {
+                    first_id: 1,
+                    last_id: 10
+                },
+                JobTrials {
+                    first_id: 2,
+                    last_id: 5
+                },
+                JobTrials {
+                    first_id: 1,
+                    last_id: 1
+                },
+            ]
+        );
+        assert_eq!(trials.ntrials(), 15);
+    }
+
+    #[test]
+    fn per_job() {
+        // two jobs with 3 and 1 trials, each normalised to xs = 4
+        let mut events = vec![event(1, 1.), event(3, 3.), event(1, 4.)];
+        let trials = Trials::from_ids(events.iter().map(|e| e.id));
+        let mut normaliser = Normaliser::per_job(&trials).with_factor(2.);
+        for event in &mut events {
+            normaliser.normalise(event);
+        }
+        let xs: f64 = events.iter().map(|e| e.weight).sum();
+        assert_eq!(xs, 8.);
+        assert_eq!(events[0].weight, 1.5);
+        assert_eq!(events[0].weight2, 1.5);
+        assert_eq!(events[0].me_weight, 3.);
+        assert_eq!(events[0].me_weight2, 3.);
+        assert_eq!(events[0].user_weights, [1.5, -1.5]);
+        assert_eq!(events[2].weight, 2.);
+    }
+
+    #[test]
+    fn per_trial() {
+        let mut ev = event(4, 8.);
+        let trials = Trials::from_ids([1, 4]);
+        Normaliser::per_trial(&trials).unwrap().normalise(&mut ev);
+        assert_eq!(ev.weight, 2.);
+        Normaliser::to_xsection(2., 3.).unwrap().normalise(&mut ev);
+        assert_eq!(ev.weight, 3.);
+
+        assert_eq!(Normaliser::per_trial(&Trials::new()), None);
+        assert_eq!(Normaliser::to_xsection(0., 3.), None);
+    }
+}
@@ -2,9 +2,16 @@ extern "C" {
 #include "ntuplereader.h"
 }
 
+#include <cassert>
+#include <memory>
 #include <mutex>
+#include <string>
 
 #include "TFile.h"
+#include "TLeaf.h"
+#include "TMemFile.h"
+#include "TNamed.h"
+#include "TParameter.h"
 #include "TTree.h"
 
 #include "root_interface.hh"
@@ -12,94 +19,162 @@ extern "C" {
 using namespace ntuple;
 
 struct NTupleReader {
-  TFile file;
+  std::unique_ptr<TFile> file;
   RootEvent event;
   TTree* tree;
   bool legacy_fmt;
+  // whether `alphasPower` is stored as a single character
+  bool char_alphas_power;
+  // value returned by the last `ntuple_reader_get_string`
+  std::string run_info_string;
 };
 
-extern "C" {
-NTupleReaderCreateResult ntuple_create_reader(char const *file) {
-  try {
-    std::lock_guard<std::mutex> lock{file_mutex};
-    auto *reader = new NTupleReader{
-      {file, "READ"},
-      RootEvent{},
-      nullptr,
-      false
-    };
-    if(!reader || !reader->file.IsOpen()) {
+namespace {
+  // Set up a reader for the file returned by `make_file`
+  template<class MakeFile>
+  NTupleReaderCreateResult create_reader(MakeFile make_file) {
+    try {
+      std::lock_guard<std::mutex> lock{file_mutex};
+      auto reader = std::unique_ptr<NTupleReader>(new NTupleReader{
+        make_file(),
+        RootEvent{},
+        nullptr,
+        false,
+        false,
+        {}
+      });
+      if(!reader->file || !reader->file->IsOpen()) {
+        return NTupleReaderCreateResult {
+          nullptr,
+          OPEN_FAILED
+        };
+      }
+      reader->tree = dynamic_cast<TTree*>(reader->file->Get("BHSntuples"));
+      if(!reader->tree) {
+        // fallback: sometimes the name is a bit different
+        reader->tree = dynamic_cast<TTree*>(reader->file->Get("t3"));
+        reader->legacy_fmt = true;
+      }
+      if(!reader->tree) {
+        return NTupleReaderCreateResult {
+          nullptr,
+          NO_TTREE
+        };
+      }
+
+      reader->event.part.back() = '\0'; // ensure c string is null terminated
+
+      auto & ev = reader->event;
+      auto & tree = *reader->tree;
+      tree.SetBranchAddress("id", &ev.id);
+      tree.SetBranchAddress("nparticle", &ev.nparticle);
+      tree.SetBranchAddress("px", ev.px.data());
+      tree.SetBranchAddress("py", ev.py.data());
+      tree.SetBranchAddress("pz", ev.pz.data());
+      tree.SetBranchAddress("E", ev.E.data());
+      tree.SetBranchAddress("alphas", &ev.alphas);
+      tree.SetBranchAddress("kf", ev.kf.data());
+      tree.SetBranchAddress("weight", &ev.weight);
+      tree.SetBranchAddress("weight2", &ev.weight2);
+      tree.SetBranchAddress("me_wgt", &ev.me_wgt);
+      tree.SetBranchAddress("me_wgt2", &ev.me_wgt2);
+      tree.SetBranchAddress("x1", &ev.x1);
+      tree.SetBranchAddress("x2", &ev.x2);
+      tree.SetBranchAddress("x1p", &ev.x1p);
+      tree.SetBranchAddress("x2p", &ev.x2p);
+      tree.SetBranchAddress("id1", &ev.id1);
+      tree.SetBranchAddress("id2", &ev.id2);
+      tree.SetBranchAddress("fac_scale", &ev.fac_scale);
+      tree.SetBranchAddress("ren_scale", &ev.ren_scale);
+      tree.SetBranchAddress("nuwgt", &ev.nuwgt);
+      tree.SetBranchAddress("usr_wgts", ev.usr_wgts.data());
+      tree.SetBranchAddress("part", &ev.part);
+      // some legacy files store `alphasPower` as a single character
+      TLeaf const * alphas_power = tree.GetLeaf("alphasPower");
+      reader->char_alphas_power =
+        alphas_power && std::string{alphas_power->GetTypeName()} == "Char_t";
+      if(reader->char_alphas_power) {
+        tree.SetBranchAddress("alphasPower", &ev.alphas_power_char);
+      } else {
+        tree.SetBranchAddress("alphasPower", &ev.alphas_power);
+      }
+
+      return NTupleReaderCreateResult {
+        reader.release(),
+        NONE
+      };
+    } catch (std::exception const & e) {
+      set_last_error(e);
       return NTupleReaderCreateResult {
         nullptr,
-        OPEN_FAILED
+        EXCEPTION
       };
-    }
-    reader->tree = dynamic_cast<TTree*>(reader->file.Get("BHSntuples"));
-    if(!reader->tree) {
-      // fallback: sometimes the name is a bit different
-      reader->tree = dynamic_cast<TTree*>(reader->file.Get("t3"));
-      reader->legacy_fmt = true;
-    }
-    if(!reader->tree) {
+    } catch (...) {
+      set_last_error("unknown exception");
       return NTupleReaderCreateResult {
         nullptr,
-        NO_TTREE
+        EXCEPTION
       };
     }
+  }
+}
 
-    reader->event.part.back() = '\0'; // ensure c string is null terminated
-
-    auto & ev = reader->event;
-    auto & tree = *reader->tree;
-    tree.SetBranchAddress("id", &ev.id);
-    tree.SetBranchAddress("nparticle", &ev.nparticle);
-    tree.SetBranchAddress("px", ev.px.data());
-    tree.SetBranchAddress("py", ev.py.data());
-    tree.SetBranchAddress("pz", ev.pz.data());
-    tree.SetBranchAddress("E", ev.E.data());
-    tree.SetBranchAddress("alphas", &ev.alphas);
-    tree.SetBranchAddress("kf", ev.kf.data());
-    tree.SetBranchAddress("weight", &ev.weight);
-    tree.SetBranchAddress("weight2", &ev.weight2);
-    tree.SetBranchAddress("me_wgt", &ev.me_wgt);
-    tree.SetBranchAddress("me_wgt2", &ev.me_wgt2);
-    tree.SetBranchAddress("x1", &ev.x1);
-    tree.SetBranchAddress("x2", &ev.x2);
-    tree.SetBranchAddress("x1p", &ev.x1p);
-    tree.SetBranchAddress("x2p", &ev.x2p);
-    tree.SetBranchAddress("id1", &ev.id1);
-    tree.SetBranchAddress("id2", &ev.id2);
-    tree.SetBranchAddress("fac_scale", &ev.fac_scale);
-    tree.SetBranchAddress("ren_scale", &ev.ren_scale);
-    tree.SetBranchAddress("nuwgt", &ev.nuwgt);
-    tree.SetBranchAddress("usr_wgts", ev.usr_wgts.data());
-    tree.SetBranchAddress("part", &ev.part);
-    if(reader->legacy_fmt) {
-      tree.SetBranchAddress("alphasPower", &ev.alphas_power_char);
-    } else {
-      tree.SetBranchAddress("alphasPower", &ev.alphas_power);
-    }
+namespace {
+  // Look up an entry of type `T` in the run information directory
+  //
+  // Calls `use` with the entry if it exists and returns 1, otherwise
+  // returns 0. Returns -1 if an exception occurs.
+  template<class T, class Use>
+  int get_run_info(NTupleReader * reader, char const * key, Use use) {
+    assert(reader);
 
-    return NTupleReaderCreateResult {
-      reader,
-      NONE
-    };
-  } catch (...) {
-    return NTupleReaderCreateResult {
-      nullptr,
-      EXCEPTION
-    };
+    try {
+      std::lock_guard<std::mutex> lock{file_mutex};
+      reader->file->cd();
+      TDirectory * dir = reader->file->GetDirectory(RUN_INFO_DIR);
+      if(!dir) return 0;
+      // objects other than trees are owned by the caller
+      std::unique_ptr<TObject> obj{dir->Get(key)};
+      auto const * entry = dynamic_cast<T const*>(obj.get());
+      if(!entry) return 0;
+      use(*entry);
+      return 1;
+    } catch(std::exception const & e) {
+      set_last_error(e);
+      return -1;
+    } catch(...) {
+      set_last_error("unknown exception");
+      return -1;
+    }
   }
 }
 
+extern "C" {
+NTupleReaderCreateResult ntuple_create_reader(char const *file) {
+  return create_reader([file] {
+    return std::make_unique<TFile>(file, "READ");
+  });
+}
+
+NTupleReaderCreateResult ntuple_create_reader_from_buffer(
+  char const *buffer, int64_t const size
+) {
+  return create_reader([buffer, size] {
+    // TMemFile copies the buffer and does not modify it
+    return std::make_unique<TMemFile>(
+      "ntuple_buffer", const_cast<char*>(buffer), size, "READ"
+    );
+  });
+}
+
 void ntuple_delete_reader(NTupleReader * reader) {
   assert(reader);
   assert(reader->tree);
 
   try {
     std::lock_guard<std::mutex> lock{file_mutex};
-    reader->file.cd();
-    reader->file.Close();
+    reader->file->cd();
+    reader->file->Close();
   } catch(...) {
     // let no exception escape,
     // it's undefined behaviour to let it propagate to rust
@@ -114,13 +189,82 @@ int64_t ntuple_num_events(NTupleReader * reader) {
 
   try {
     std::lock_guard<std::mutex> lock{file_mutex};
-    reader->file.cd();
+    reader->file->cd();
     return reader->tree->GetEntries();
   } catch(...) {
     return -1;
   }
 }
 
+char const * ntuple_reader_tree_name(NTupleReader * reader) {
+  assert(reader);
+  assert(reader->tree);
+  return reader->tree->GetName();
+}
+
+char const * ntuple_reader_title(NTupleReader * reader) {
+  assert(reader);
+  assert(reader->tree);
+  return reader->tree->GetTitle();
+}
+
+int ntuple_reader_is_legacy(NTupleReader * reader) {
+  assert(reader);
+  return reader->legacy_fmt;
+}
+
+int64_t ntuple_reader_num_leaves(NTupleReader * reader) {
+  assert(reader);
+  assert(reader->tree);
+
+  try {
+    std::lock_guard<std::mutex> lock{file_mutex};
+    reader->file->cd();
+    return reader->tree->GetListOfLeaves()->GetEntriesFast();
+  } catch(std::exception const & e) {
+    set_last_error(e);
+    return -1;
+  } catch(...) {
+    set_last_error("unknown exception");
+    return -1;
+  }
+}
+
+int ntuple_reader_leaf_info(
+  NTupleReader * reader, int64_t const idx, NTupleLeafInfo * info
+) {
+  assert(reader);
+  assert(reader->tree);
+  assert(info);
+
+  try {
+    std::lock_guard<std::mutex> lock{file_mutex};
+    reader->file->cd();
+    auto * leaves = reader->tree->GetListOfLeaves();
+    if(idx < 0 || idx >= leaves->GetEntriesFast()) {
+      set_last_error("leaf index out of range");
+      return -1;
+    }
+    auto const * leaf = dynamic_cast<TLeaf const*>(leaves->At(idx));
+    if(!leaf) {
+      set_last_error("not a leaf");
+      return -1;
+    }
+    info->branch = leaf->GetBranch()->GetName();
+    info->leaf = leaf->GetName();
+    info->type = leaf->GetTypeName();
+    auto const * count = leaf->GetLeafCount();
+    info->count = count ? count->GetName() : nullptr;
+    return 0;
+  } catch(std::exception const & e) {
+    set_last_error(e);
+    return -1;
+  } catch(...) {
+    set_last_error("unknown exception");
+    return -1;
+  }
+}
+
 NTupleReadResult ntuple_read_event(NTupleReader * reader, int64_t const idx) {
   assert(reader);
   assert(reader->tree);
@@ -133,7 +277,7 @@ NTupleReadResult ntuple_read_event(NTupleReader * reader, int64_t const idx) {
 
   try {
     std::lock_guard<std::mutex> lock{file_mutex};
-    reader->file.cd();
+    reader->file->cd();
 
     const auto read = reader->tree->GetEntry(idx);
     switch(read) {
@@ -169,7 +313,7 @@ NTupleReadResult ntuple_read_event(NTupleReader * reader, int64_t const idx) {
     event.nuwgt = ev.nuwgt;
     event.usr_wgts = ev.usr_wgts.data();
     event.part = ev.part[0];
-    if(reader->legacy_fmt) {
+    if(reader->char_alphas_power) {
       event.alphas_power = ev.alphas_power_char;
     } else {
       event.alphas_power = ev.alphas_power;
@@ -194,10 +338,42 @@ NTupleReadResult ntuple_read_event(NTupleReader * reader, int64_t const idx) {
       return result;
     }
 
+    return result;
+  } catch(std::exception const & e) {
+    set_last_error(e);
+    status = READ_EXCEPTION;
     return result;
   } catch(...) {
+    set_last_error("unknown exception");
     status = READ_EXCEPTION;
     return result;
   }
 }
+
+int ntuple_reader_get_string(
+  NTupleReader * reader, char const * key, char const ** value
+) {
+  return get_run_info<TNamed>(reader, key, [reader, value](TNamed const & entry) {
+    reader->run_info_string = entry.GetTitle();
+    *value = reader->run_info_string.c_str();
+  });
+}
+
+int ntuple_reader_get_double(
+  NTupleReader * reader, char const * key, double * value
+) {
+  return get_run_info<TParameter<Double_t>>(
+    reader, key,
+    [value](TParameter<Double_t> const & entry) { *value = entry.GetVal(); }
+  );
+}
+
+int ntuple_reader_get_int(
+  NTupleReader * reader, char const * key, int64_t * value
+) {
+  return get_run_info<TParameter<Long64_t>>(
+    reader, key,
+    [value](TParameter<Long64_t> const & entry) { *value = entry.GetVal(); }
+  );
+}
 }
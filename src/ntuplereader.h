@@ -28,9 +28,50 @@ typedef struct {
 } NTupleReaderCreateResult;
 
 NTupleReaderCreateResult ntuple_create_reader(char const* file);
+/* Read from an in-memory ROOT file. The buffer is copied. */
+NTupleReaderCreateResult ntuple_create_reader_from_buffer(
+  char const* buffer, int64_t size
+);
 void ntuple_delete_reader(NTupleReader* reader);
 
 int64_t ntuple_num_events(NTupleReader* reader);
+
+/* Name and title of the TTree.
+ *
+ * The strings remain valid until the reader is deleted.
+ */
+char const* ntuple_reader_tree_name(NTupleReader* reader);
+char const* ntuple_reader_title(NTupleReader* reader);
+/* Whether the file uses the legacy format with a "t3" TTree */
+int ntuple_reader_is_legacy(NTupleReader* reader);
+
+typedef struct {
+  char const* branch;
+  char const* leaf;
+  /* ROOT type name, e.g. "Float_t" */
+  char const* type;
+  /* Name of the leaf holding the array length, NULL for scalars */
+  char const* count;
+} NTupleLeafInfo;
+
+/* Number of leaves in the TTree, or -1 on failure */
+int64_t ntuple_reader_num_leaves(NTupleReader* reader);
+/* Information about the leaf with the given index.
+ *
+ * Returns 0 on success and -1 on failure. The strings remain valid
+ * until the reader is deleted.
+ */
+int ntuple_reader_leaf_info(NTupleReader* reader, int64_t idx, NTupleLeafInfo* info);
 NTupleReadResult ntuple_read_event(NTupleReader* reader, int64_t idx);
 
+/* Look up run information in the "RunInfo" directory of the file.
+ *
+ * Returns 1 if an entry with the given key and type exists, 0 if it
+ * does not, and -1 on failure. Strings remain valid until the next
+ * call to `ntuple_reader_get_string` for the same reader.
+ */
+int ntuple_reader_get_string(NTupleReader* reader, char const* key, char const** value);
+int ntuple_reader_get_double(NTupleReader* reader, char const* key, double* value);
+int ntuple_reader_get_int(NTupleReader* reader, char const* key, int64_t* value);
+
 #endif /* NTUPLEREADER_H */
@@ -5,8 +5,12 @@ extern "C" {
 #include <cassert>
 #include <memory>
 #include <mutex>
+#include <vector>
 
 #include "TFile.h"
+#include "TMemFile.h"
+#include "TNamed.h"
+#include "TParameter.h"
 #include "TTree.h"
 
 #include "root_interface.hh"
@@ -14,89 +18,152 @@ extern "C" {
 using namespace ntuple;
 
 struct NTupleWriter {
-  TFile file;
+  std::unique_ptr<TFile> file;
   RootEvent event;
   // we use a pointer here to work around a presumed ROOT bug,
   // where `tree` is not associated with `file`,
   // although `file` is guaranteed to be constructed first
   // a raw pointer is the correct type, since the file silently takes ownership
   TTree* tree;
+  // contents of a finished in-memory file
+  std::vector<char> buffer;
+  bool finished;
 };
 
-extern "C" {
-NTupleWriterCreateResult ntuple_create_writer(char const *file, char const *title) {
-  try {
-    std::lock_guard<std::mutex> lock{file_mutex};
-    auto *writer = new NTupleWriter{
-      {file, "RECREATE"},
-      RootEvent{},
-      nullptr
-    };
-    if(!writer || !writer->file.IsOpen()) {
+namespace {
+  // Set up a writer for the file returned by `make_file`
+  template<class MakeFile>
+  NTupleWriterCreateResult create_writer(MakeFile make_file, char const *title) {
+    try {
+      std::lock_guard<std::mutex> lock{file_mutex};
+      auto writer = std::unique_ptr<NTupleWriter>(new NTupleWriter{
+        make_file(),
+        RootEvent{},
+        nullptr,
+        {},
+        false
+      });
+      if(!writer->file || !writer->file->IsOpen()) {
+        return NTupleWriterCreateResult {
+          nullptr,
+          OPEN_FAILED
+        };
+      }
+      writer->file->cd();
+      writer->tree = new TTree{"BHSntuples", title};
+      if(!writer->tree) {
+        return NTupleWriterCreateResult {
+          nullptr,
+          NO_TTREE,
+        };
+      }
+      writer->event.part.back() = '\0'; // ensure c string is null terminated
+
+      auto &ev = writer->event;
+      auto &tree = *writer->tree;
+      tree.Branch("id", &ev.id, "id/I");
+      tree.Branch("nparticle", &ev.nparticle, "nparticle/I");
+      tree.Branch("px", ev.px.data(), "px[nparticle]/F");
+      tree.Branch("py", ev.py.data(), "py[nparticle]/F");
+      tree.Branch("pz", ev.pz.data(), "pz[nparticle]/F");
+      tree.Branch("E", ev.E.data(), "E[nparticle]/F");
+      tree.Branch("alphas", &ev.alphas, "alphas/D");
+      tree.Branch("kf", ev.kf.data(), "kf[nparticle]/I");
+      tree.Branch("weight", &ev.weight, "weight/D");
+      tree.Branch("weight2", &ev.weight2, "weight2/D");
+      // intentional typo 'me_wtg' for compatibility with existing files
+      tree.Branch("me_wgt", &ev.me_wgt, "me_wtg/D");
+      tree.Branch("me_wgt2", &ev.me_wgt2, "me_wtg2/D");
+      tree.Branch("x1", &ev.x1, "x1/D");
+      tree.Branch("x2", &ev.x2, "x2/D");
+      tree.Branch("x1p", &ev.x1p, "x1p/D");
+      tree.Branch("x2p", &ev.x2p, "x2p/D");
+      tree.Branch("id1", &ev.id1, "id1/I");
+      tree.Branch("id2", &ev.id2, "id2/I");
+      tree.Branch("fac_scale", &ev.fac_scale, "fac_scale/D");
+      tree.Branch("ren_scale", &ev.ren_scale, "ren_scale/D");
+      tree.Branch("nuwgt", &ev.nuwgt, "nuwgt/I");
+      tree.Branch("usr_wgts", ev.usr_wgts.data(), "usr_wgts[nuwgt]/D");
+      tree.Branch("part", ev.part.data(), "part/C");
+      tree.Branch("alphasPower", &ev.alphas_power, "alphasPower/S");
+
+
+      return NTupleWriterCreateResult {
+        writer.release(),
+        NONE
+      };
+    } catch (std::exception const & e) {
+      set_last_error(e);
       return NTupleWriterCreateResult {
         nullptr,
-        OPEN_FAILED
+        EXCEPTION
       };
-    }
-    writer->file.cd();
-    writer->tree = new TTree{"BHSntuples", title};
-    if(!writer->tree) {
+    } catch (...) {
+      set_last_error("unknown exception");
       return NTupleWriterCreateResult {
         nullptr,
-        NO_TTREE,
+        EXCEPTION
       };
     }
-    writer->event.part.back() = '\0'; // ensure c string is null terminated
-
-    auto &ev = writer->event;
-    auto &tree = *writer->tree;
-    tree.Branch("id", &ev.id, "id/I");
-    tree.Branch("nparticle", &ev.nparticle, "nparticle/I");
-    tree.Branch("px", ev.px.data(), "px[nparticle]/F");
-    tree.Branch("py", ev.py.data(), "py[nparticle]/F");
-    tree.Branch("pz", ev.pz.data(), "pz[nparticle]/F");
-    tree.Branch("E", ev.E.data(), "E[nparticle]/F");
-    tree.Branch("alphas", &ev.alphas, "alphas/D");
-    tree.Branch("kf", ev.kf.data(), "kf[nparticle]/I");
-    tree.Branch("weight", &ev.weight, "weight/D");
-    tree.Branch("weight2", &ev.weight2, "weight2/D");
-    // intentional typo 'me_wtg' for compatibility with existing files
-    tree.Branch("me_wgt", &ev.me_wgt, "me_wtg/D");
-    tree.Branch("me_wgt2", &ev.me_wgt2, "me_wtg2/D");
-    tree.Branch("x1", &ev.x1, "x1/D");
-    tree.Branch("x2", &ev.x2, "x2/D");
-    tree.Branch("x1p", &ev.x1p, "x1p/D");
-    tree.Branch("x2p", &ev.x2p, "x2p/D");
-    tree.Branch("id1", &ev.id1, "id1/I");
-    tree.Branch("id2", &ev.id2, "id2/I");
-    tree.Branch("fac_scale", &ev.fac_scale, "fac_scale/D");
-    tree.Branch("ren_scale", &ev.ren_scale, "ren_scale/D");
-    tree.Branch("nuwgt", &ev.nuwgt, "nuwgt/I");
-    tree.Branch("usr_wgts", ev.usr_wgts.data(), "usr_wgts[nuwgt]/D");
-    tree.Branch("part", ev.part.data(), "part/C");
-    tree.Branch("alphasPower", &ev.alphas_power, "alphasPower/S");
-
-    return NTupleWriterCreateResult {
-      writer,
-      NONE
-    };
-  } catch (...) {
-    return NTupleWriterCreateResult {
-      nullptr,
-      EXCEPTION
-    };
+  }
+
+  // Write `obj` to the run information directory
+  int set_run_info(NTupleWriter * writer, char const * key, TObject const & obj) {
+    assert(writer);
+    assert(!writer->finished);
+
+    try {
+      std::lock_guard<std::mutex> lock{file_mutex};
+      auto & file = *writer->file;
+      TDirectory * dir = file.GetDirectory(RUN_INFO_DIR);
+      if(!dir) dir = file.mkdir(RUN_INFO_DIR);
+      if(!dir) {
+        set_last_error("failed to create run information directory");
+        return -1;
+      }
+      const auto written = dir->WriteTObject(&obj, key, "WriteDelete");
+      file.cd();
+      if(written <= 0) {
+        set_last_error("failed to write run information");
+        return -1;
+      }
+      return 0;
+    } catch(std::exception const & e) {
+      set_last_error(e);
+      return -1;
+    } catch(...) {
+      set_last_error("unknown exception");
+      return -1;
+    }
   }
 }
 
+extern "C" {
+NTupleWriterCreateResult ntuple_create_writer(char const *file, char const *title) {
+  return create_writer(
+    [file] { return std::make_unique<TFile>(file, "RECREATE"); },
+    title
+  );
+}
+
+NTupleWriterCreateResult ntuple_create_memory_writer(char const *title) {
+  return create_writer(
+    [] { return std::make_unique<TMemFile>("ntuple_buffer", "RECREATE"); },
+    title
+  );
+}
+
 void ntuple_delete_writer(NTupleWriter * writer) {
   assert(writer);
   assert(writer->tree);
 
   try {
-    std::lock_guard<std::mutex> lock{file_mutex};
-    writer->file.cd();
-    writer->file.Write();
-    writer->file.Close();
+    if(!writer->finished) {
+      std::lock_guard<std::mutex> lock{file_mutex};
+      writer->file->cd();
+      writer->file->Write();
+      writer->file->Close();
+    }
   } catch(...) {
     // let no exception escape,
     // it's undefined behaviour to let it propagate to rust
@@ -105,6 +172,39 @@ void ntuple_delete_writer(NTupleWriter * writer) {
   delete writer;
 }
 
+int64_t ntuple_finish_writer(NTupleWriter * writer) {
+  assert(writer);
+  assert(!writer->finished);
+
+  try {
+    std::lock_guard<std::mutex> lock{file_mutex};
+    writer->finished = true;
+    writer->file->cd();
+    writer->file->Write();
+    if(writer->file->TestBit(TFile::kWriteError)) {
+      set_last_error("failed to write ROOT file");
+      return -1;
+    }
+    if(auto * memfile = dynamic_cast<TMemFile*>(writer->file.get())) {
+      writer->buffer.resize(memfile->GetSize());
+      memfile->CopyTo(writer->buffer.data(), writer->buffer.size());
+    }
+    writer->file->Close();
+    return writer->buffer.size();
+  } catch(std::exception const & e) {
+    set_last_error(e);
+    return -1;
+  } catch(...) {
+    set_last_error("unknown exception");
+    return -1;
+  }
+}
+
+char const * ntuple_writer_buffer(NTupleWriter const * writer) {
+  assert(writer);
+  return writer->buffer.data();
+}
+
 NTupleWriteResult ntuple_write_event(NTupleWriter * writer, NTupleEvent const * event) {
   assert(writer);
   assert(event);
@@ -149,11 +249,44 @@ NTupleWriteResult ntuple_write_event(NTupleWriter * writer, NTupleEvent const *
     // filling data into the tree may trigger a write,
     // so we have to lock the mutex and fix the current directory
     std::lock_guard<std::mutex> lock{file_mutex};
-    writer->file.cd();
+    writer->file->cd();
     writer->tree->Fill();
   } catch(...) {
     return WRITE_FILL_ERROR;
   }
   return WRITE_OK;
 }
+
+int ntuple_writer_set_string(
+  NTupleWriter * writer, char const * key, char const * value
+) {
+  try {
+    return set_run_info(writer, key, TNamed{key, value});
+  } catch(...) {
+    set_last_error("unknown exception");
+    return -1;
+  }
+}
+
+int ntuple_writer_set_double(
+  NTupleWriter * writer, char const * key, double const value
+) {
+  try {
+    return set_run_info(writer, key, TParameter<Double_t>{key, value});
+  } catch(...) {
+    set_last_error("unknown exception");
+    return -1;
+  }
+}
+
+int ntuple_writer_set_int(
+  NTupleWriter * writer, char const * key, int64_t const value
+) {
+  try {
+    return set_run_info(writer, key, TParameter<Long64_t>{key, value});
+  } catch(...) {
+    set_last_error("unknown exception");
+    return -1;
+  }
+}
 }
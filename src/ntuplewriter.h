@@ -21,8 +21,28 @@ typedef struct {
 } NTupleWriterCreateResult;
 
 NTupleWriterCreateResult ntuple_create_writer(char const *file, char const *title);
+/* Write to an in-memory ROOT file */
+NTupleWriterCreateResult ntuple_create_memory_writer(char const *title);
 void ntuple_delete_writer(NTupleWriter *);
 
+/* Write all data and close the file.
+ *
+ * Returns the size of the in-memory file, 0 for a file on disk, or -1
+ * on failure. No further events may be written after this call.
+ */
+int64_t ntuple_finish_writer(NTupleWriter * writer);
+/* Contents of a finished in-memory file */
+char const * ntuple_writer_buffer(NTupleWriter const * writer);
+
 NTupleWriteResult ntuple_write_event(NTupleWriter * writer, NTupleEvent const * event);
 
+/* Store run information in the "RunInfo" directory of the file.
+ *
+ * Existing entries with the same key are replaced. Returns 0 on
+ * success and -1 on failure.
+ */
+int ntuple_writer_set_string(NTupleWriter * writer, char const * key, char const * value);
+int ntuple_writer_set_double(NTupleWriter * writer, char const * key, double value);
+int ntuple_writer_set_int(NTupleWriter * writer, char const * key, int64_t value);
+
 #endif /* NTUPLEWRITER_H */
@@ -0,0 +1,356 @@
+use std::{
+    f64::consts::PI,
+    iter::Sum,
+    ops::{Add, AddAssign, Index, Mul, Neg, Sub, SubAssign},
+};
+
+use crate::Event;
+
+/// Four-momentum with components (E, px, py, pz)
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct FourMomentum(pub [f64; 4]);
+
+impl FourMomentum {
+    pub fn new(e: f64, px: f64, py: f64, pz: f64) -> Self {
+        Self([e, px, py, pz])
+    }
+
+    pub fn e(&self) -> f64 {
+        self.0[0]
+    }
+
+    pub fn px(&self) -> f64 {
+        self.0[1]
+    }
+
+    pub fn py(&self) -> f64 {
+        self.0[2]
+    }
+
+    pub fn pz(&self) -> f64 {
+        self.0[3]
+    }
+
+    /// Squared transverse momentum
+    pub fn pt2(&self) -> f64 {
+        self.px() * self.px() + self.py() * self.py()
+    }
+
+    /// Transverse momentum
+    pub fn pt(&self) -> f64 {
+        self.pt2().sqrt()
+    }
+
+    /// Squared spatial momentum
+    pub fn p2(&self) -> f64 {
+        self.pt2() + self.pz() * self.pz()
+    }
+
+    /// Absolute value of the spatial momentum
+    pub fn p(&self) -> f64 {
+        self.p2().sqrt()
+    }
+
+    /// Squared invariant mass
+    pub fn m2(&self) -> f64 {
+        self.e() * self.e() - self.p2()
+    }
+
+    /// Invariant mass
+    ///
+    /// For space-like momenta this is the negative square root of the
+    /// absolute value of the squared mass.
+    pub fn m(&self) -> f64 {
+        let m2 = self.m2();
+        m2.abs().sqrt().copysign(m2)
+    }
+
+    /// Rapidity
+    pub fn rapidity(&self) -> f64 {
+        0.5 * ((self.e() + self.pz()) / (self.e() - self.pz())).ln()
+    }
+
+    /// Pseudorapidity
+    pub fn pseudorapidity(&self) -> f64 {
+        (self.pz() / self.pt()).asinh()
+    }
+
+    /// Azimuthal angle in the range [-π, π]
+    pub fn phi(&self) -> f64 {
+        self.py().atan2(self.px())
+    }
+
+    /// Polar angle with respect to the beam axis
+    pub fn theta(&self) -> f64 {
+        self.pt().atan2(self.pz())
+    }
+
+    /// Distance in the rapidity-azimuth plane
+    pub fn delta_r(&self, other: &FourMomentum) -> f64 {
+        let dy = self.rapidity() - other.rapidity();
+        let dphi = delta_phi(self.phi(), other.phi());
+        (dy * dy + dphi * dphi).sqrt()
+    }
+
+    /// Lorentz boost with velocity `beta`
+    ///
+    /// A momentum at rest ends up moving with velocity `beta`.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `|beta| < 1`.
+    pub fn boost(&self, beta: [f64; 3]) -> Self {
+        let b2: f64 = beta.iter().map(|b| b * b).sum();
+        assert!(b2 < 1., "Boost velocity {beta:?} is not below 1");
+        if b2 == 0. {
+            return *self;
+        }
+        let gamma = 1. / (1. - b2).sqrt();
+        let bp =
+            beta[0] * self.px() + beta[1] * self.py() + beta[2] * self.pz();
+        let coeff = (gamma - 1.) * bp / b2 + gamma * self.e();
+        Self::new(
+            gamma * (self.e() + bp),
+            self.px() + coeff * beta[0],
+            self.py() + coeff * beta[1],
+            self.pz() + coeff * beta[2],
+        )
+    }
+
+    /// Rotation by `angle` around `axis`
+    ///
+    /// The axis does not have to be normalised.
+    pub fn rotate(&self, axis: [f64; 3], angle: f64) -> Self {
+        let norm = axis.iter().map(|k| k * k).sum::<f64>().sqrt();
+        let k = axis.map(|k| k / norm);
+        let v = [self.px(), self.py(), self.pz()];
+        let (sin, cos) = angle.sin_cos();
+        let kv = k[0] * v[0] + k[1] * v[1] + k[2] * v[2];
+        let kxv = [
+            k[1] * v[2] - k[2] * v[1],
+            k[2] * v[0] - k[0] * v[2],
+            k[0] * v[1] - k[1] * v[0],
+        ];
+        let rot = |i: usize| v[i] * cos + kxv[i] * sin + k[i] * kv * (1. - cos);
+        Self::new(self.e(), rot(0), rot(1), rot(2))
+    }
+
+    /// Rotation by `angle` around the beam axis
+    pub fn rotate_z(&self, angle: f64) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self::new(
+            self.e(),
+            cos * self.px() - sin * self.py(),
+            sin * self.px() + cos * self.py(),
+            self.pz(),
+        )
+    }
+}
+
+/// Difference between two azimuthal angles in the range [-π, π]
+pub fn delta_phi(phi1: f64, phi2: f64) -> f64 {
+    let dphi = (phi1 - phi2).rem_euclid(2. * PI);
+    if dphi > PI {
+        dphi - 2. * PI
+    } else {
+        dphi
+    }
+}
+
+impl From<[f64; 4]> for FourMomentum {
+    fn from(p: [f64; 4]) -> Self {
+        Self(p)
+    }
+}
+
+impl From<FourMomentum> for [f64; 4] {
+    fn from(p: FourMomentum) -> Self {
+        p.0
+    }
+}
+
+impl Index<usize> for FourMomentum {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.0[i]
+    }
+}
+
+impl AddAssign for FourMomentum {
+    fn add_assign(&mut self, rhs: FourMomentum) {
+        for (p, q) in self.0.iter_mut().zip(rhs.0) {
+            *p += q;
+        }
+    }
+}
+
+impl SubAssign for FourMomentum {
+    fn sub_assign(&mut self, rhs: FourMomentum) {
+        for (p, q) in self.0.iter_mut().zip(rhs.0) {
+            *p -= q;
+        }
+    }
+}
+
+impl Add for FourMomentum {
+    type Output = Self;
+
+    fn add(mut self, rhs: FourMomentum) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl Sub for FourMomentum {
+    type Output = Self;
+
+    fn sub(mut self, rhs: FourMomentum) -> Self::Output {
+        self -= rhs;
+        self
+    }
+}
+
+impl Neg for FourMomentum {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self(self.0.map(|p| -p))
+    }
+}
+
+impl Mul<f64> for FourMomentum {
+    type Output = Self;
+
+    fn mul(self, rhs: f64) -> Self::Output {
+        Self(self.0.map(|p| rhs * p))
+    }
+}
+
+impl Mul<FourMomentum> for f64 {
+    type Output = FourMomentum;
+
+    fn mul(self, rhs: FourMomentum) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Sum for FourMomentum {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), |acc, p| acc + p)
+    }
+}
+
+/// View of an outgoing particle in an [Event]
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct Particle {
+    /// Particle id according to the PDG Monte Carlo numbering scheme
+    pub pdg: i32,
+    pub p: FourMomentum,
+}
+
+impl Event {
+    /// Outgoing particles
+    ///
+    /// Momenta are converted to double precision. If the particle ids
+    /// and momentum components differ in length, the shortest of them
+    /// determines the number of particles.
+    pub fn particles(
+        &self,
+    ) -> impl ExactSizeIterator<Item = Particle> + Clone + '_ {
+        self.pdg_code
+            .iter()
+            .zip(&self.energy)
+            .zip(&self.px)
+            .zip(&self.py)
+            .zip(&self.pz)
+            .map(|((((&pdg, &e), &px), &py), &pz)| Particle {
+                pdg,
+                p: FourMomentum::new(e as f64, px as f64, py as f64, pz as f64),
+            })
+    }
+
+    /// Momentum of the `i`th outgoing particle
+    pub fn momentum(&self, i: usize) -> FourMomentum {
+        FourMomentum::new(
+            self.energy[i] as f64,
+            self.px[i] as f64,
+            self.py[i] as f64,
+            self.pz[i] as f64,
+        )
+    }
+
+    /// Set the momentum of the `i`th outgoing particle
+    ///
+    /// Momenta are stored in single precision.
+    pub fn set_momentum(&mut self, i: usize, p: FourMomentum) {
+        self.energy[i] = p.e() as f32;
+        self.px[i] = p.px() as f32;
+        self.py[i] = p.py() as f32;
+        self.pz[i] = p.pz() as f32;
+    }
+
+    /// Sum of all outgoing momenta
+    pub fn total_momentum(&self) -> FourMomentum {
+        self.particles().map(|p| p.p).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinematics() {
+        let p = FourMomentum::new(5., 0., 3., 4.);
+        assert_eq!(p.pt(), 3.);
+        assert_eq!(p.m(), 0.);
+        assert_eq!(p.phi(), PI / 2.);
+        assert!((p.rapidity() - 3f64.ln()).abs() < 1e-15);
+        assert!((p.pseudorapidity() - p.rapidity()).abs() < 1e-15);
+
+        let q = FourMomentum::new(5., 0., -3., 4.);
+        assert!((p.delta_r(&q) - PI).abs() < 1e-15);
+        assert_eq!((p + q).m(), 6.);
+        assert!((delta_phi(3., -3.) - (6. - 2. * PI)).abs() < 1e-15);
+    }
+
+    #[test]
+    fn mismatched_lengths() {
+        let ev = Event {
+            pdg_code: vec![21, 21, 1],
+            px: vec![1., 2.],
+            py: vec![0.; 3],
+            pz: vec![0.; 3],
+            energy: vec![3.; 4],
+            ..Default::default()
+        };
+        assert_eq!(ev.particles().len(), 2);
+        assert_eq!(ev.total_momentum(), FourMomentum::new(6., 3., 0., 0.));
+    }
+
+    #[test]
+    fn transformations() {
+        let close = |p: FourMomentum, q: FourMomentum| {
+            (0..4).all(|i| (p[i] - q[i]).abs() < 1e-12)
+        };
+        let p = FourMomentum::new(5., 1., 2., 3.);
+        let beta = [0.1, -0.2, 0.3];
+        let q = p.boost(beta);
+        assert!((q.m2() - p.m2()).abs() < 1e-12);
+        assert!(close(q.boost(beta.map(|b| -b)), p));
+        let rest = FourMomentum::new(2., 0., 0., 0.);
+        let moving = rest.boost([0., 0., 0.6]);
+        assert!(close(moving, FourMomentum::new(2.5, 0., 0., 1.5)));
+
+        assert!(close(
+            p.rotate_z(PI / 2.),
+            FourMomentum::new(5., -2., 1., 3.)
+        ));
+        assert!(close(p.rotate([0., 0., 2.], PI / 2.), p.rotate_z(PI / 2.)));
+        assert!(close(
+            p.rotate([1., 0., 0.], PI),
+            FourMomentum::new(5., 1., -2., -3.)
+        ));
+    }
+}
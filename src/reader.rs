@@ -1,54 +1,272 @@
 use std::{
-    ffi::CString,
+    ffi::{c_char, CStr, CString},
+    io::{self, Read},
+    ops::Range,
     os::unix::prelude::OsStrExt,
     path::{Path, PathBuf},
+    ptr,
 };
 
+use log::warn;
+
 use thiserror::Error;
 
 use crate::{
     bindings::{
-        ntuple_create_reader, ntuple_delete_reader, ntuple_num_events,
-        ntuple_read_event, NTupleCreateError, NTupleReadStatus,
+        last_error, ntuple_create_reader, ntuple_create_reader_from_buffer,
+        ntuple_delete_reader, ntuple_num_events, ntuple_read_event,
+        ntuple_reader_is_legacy, ntuple_reader_leaf_info,
+        ntuple_reader_num_leaves, ntuple_reader_title, ntuple_reader_tree_name,
+        NTupleCreateError, NTupleLeafInfo, NTupleReadStatus,
         NTupleReaderCreateResult,
     },
+    filter::{Filter, Filtered, ParseError},
+    group::Groups,
+    run_info::{self, RunInfo, RunInfoError},
     Event,
 };
 
+/// What to do when an entry cannot be read
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum OnError {
+    /// Return the error and continue with the next entry
+    #[default]
+    Continue,
+    /// Return the error and stop reading
+    Stop,
+    /// Log the error and continue with the next entry
+    Skip,
+    /// Continue with the next entry and keep the error
+    ///
+    /// The errors can be retrieved with [Reader::errors].
+    Collect,
+}
+
+/// Options for opening a [Reader]
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ReaderOptions {
+    pub on_error: OnError,
+}
+
+impl ReaderOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set what to do when an entry cannot be read
+    pub fn on_error(mut self, on_error: OnError) -> Self {
+        self.on_error = on_error;
+        self
+    }
+
+    /// Open a file with these options
+    pub fn open<P: AsRef<Path>>(&self, file: P) -> Result<Reader, CreateError> {
+        Reader::with_options(file, self)
+    }
+
+    /// Read an in-memory ROOT file with these options
+    pub fn open_bytes(&self, bytes: &[u8]) -> Result<Reader, CreateError> {
+        let Ok(size) = i64::try_from(bytes.len()) else {
+            return Err(CreateError::Open(MEMORY_PATH.into()));
+        };
+        let res = unsafe {
+            ntuple_create_reader_from_buffer(bytes.as_ptr().cast(), size)
+        };
+        Reader::from_create_result(res, MEMORY_PATH.into(), self)
+    }
+
+    /// Read a ROOT file from `reader` with these options
+    pub fn open_reader<R: Read>(
+        &self,
+        mut reader: R,
+    ) -> Result<Reader, CreateError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).map_err(CreateError::Io)?;
+        self.open_bytes(&bytes)
+    }
+}
+
+// Placeholder path for errors when reading from memory
+const MEMORY_PATH: &str = "<memory>";
+
 #[derive(Debug)]
 pub struct Reader {
     reader: *mut crate::bindings::NTupleReader,
     idx: i64,
+    path: PathBuf,
+    on_error: OnError,
+    stopped: bool,
+    lost: Vec<Range<i64>>,
+    errors: Vec<ReadError>,
+    reported: bool,
 }
 
 impl Reader {
+    /// Open a file with the default [ReaderOptions]
+    ///
+    /// Each entry that cannot be read yields an error, after which
+    /// reading continues with the next entry.
     pub fn new<P: AsRef<Path>>(file: P) -> Result<Self, CreateError> {
+        Self::with_options(file, &ReaderOptions::default())
+    }
+
+    pub fn with_options<P: AsRef<Path>>(
+        file: P,
+        opts: &ReaderOptions,
+    ) -> Result<Self, CreateError> {
         let filename = file.as_ref();
-        let file = match CString::new(filename.as_os_str().as_bytes()) {
-            Ok(f) => f,
-            Err(err) => panic!(
-                "Failed to create nTuple Reader from {filename:?}: Found nul byte at position {} in filename",
-                err.nul_position()
-            )
+        let Ok(file) = CString::new(filename.as_os_str().as_bytes()) else {
+            return Err(CreateError::NulByteInPath(filename.to_path_buf()));
         };
-        let NTupleReaderCreateResult { reader, error } =
-            unsafe { ntuple_create_reader(file.as_ptr()) };
+        let res = unsafe { ntuple_create_reader(file.as_ptr()) };
+        Self::from_create_result(res, filename.to_path_buf(), opts)
+    }
+
+    /// Read an in-memory ROOT file
+    ///
+    /// The data are copied. Errors refer to the path `<memory>`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CreateError> {
+        ReaderOptions::default().open_bytes(bytes)
+    }
+
+    /// Read a ROOT file from an arbitrary source, e.g. a pipe
+    ///
+    /// The whole input is read into memory first. Errors refer to the
+    /// path `<memory>`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, CreateError> {
+        ReaderOptions::default().open_reader(reader)
+    }
+
+    fn from_create_result(
+        res: NTupleReaderCreateResult,
+        path: PathBuf,
+        opts: &ReaderOptions,
+    ) -> Result<Self, CreateError> {
+        let NTupleReaderCreateResult { reader, error } = res;
         if reader.is_null() {
             let err = match error {
-                NTupleCreateError::OPEN_FAILED => {
-                    CreateError::Open(filename.to_path_buf())
-                }
+                NTupleCreateError::OPEN_FAILED => CreateError::Open(path),
                 NTupleCreateError::NO_TTREE => CreateError::NoTTree,
-                NTupleCreateError::EXCEPTION => CreateError::Exception,
+                NTupleCreateError::EXCEPTION => {
+                    CreateError::Exception(last_error())
+                }
                 _ => CreateError::Unknown,
             };
             Err(err)
         } else {
             debug_assert_eq!(error, NTupleCreateError::NONE);
-            Ok(Self { reader, idx: 0 })
+            Ok(Self {
+                reader,
+                idx: 0,
+                path,
+                on_error: opts.on_error,
+                stopped: false,
+                lost: Vec::new(),
+                errors: Vec::new(),
+                reported: false,
+            })
         }
     }
 
+    /// Path of the file we are reading from
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Title of the `TTree`
+    ///
+    /// This is the `name` passed to [Writer::new](crate::Writer::new).
+    pub fn title(&self) -> String {
+        unsafe { to_string(ntuple_reader_title(self.reader)) }
+    }
+
+    /// Name of the `TTree`
+    ///
+    /// This is `BHSntuples`, or `t3` for files in the legacy format.
+    pub fn tree_name(&self) -> String {
+        unsafe { to_string(ntuple_reader_tree_name(self.reader)) }
+    }
+
+    /// Whether the file is in the legacy format
+    ///
+    /// Legacy files have a `TTree` named `t3`. Some of them store
+    /// `alphasPower` as a single character, which is converted when
+    /// reading.
+    pub fn is_legacy(&self) -> bool {
+        unsafe { ntuple_reader_is_legacy(self.reader) != 0 }
+    }
+
+    /// Leaves of the `TTree`
+    ///
+    /// The leaves are returned in the order in which they appear in
+    /// the `TTree`.
+    pub fn leaves(&self) -> Result<Vec<Leaf>, LeafError> {
+        let nleaves = unsafe { ntuple_reader_num_leaves(self.reader) };
+        if nleaves < 0 {
+            return Err(LeafError::List(last_error()));
+        }
+        let mut leaves = Vec::with_capacity(nleaves as usize);
+        for idx in 0..nleaves {
+            let mut info = NTupleLeafInfo {
+                branch: ptr::null(),
+                leaf: ptr::null(),
+                type_: ptr::null(),
+                count: ptr::null(),
+            };
+            let res =
+                unsafe { ntuple_reader_leaf_info(self.reader, idx, &mut info) };
+            if res != 0 {
+                return Err(LeafError::Inspect(idx, last_error()));
+            }
+            let length = (!info.count.is_null())
+                .then(|| unsafe { to_string(info.count) });
+            leaves.push(Leaf {
+                branch: unsafe { to_string(info.branch) },
+                name: unsafe { to_string(info.leaf) },
+                leaf_type: unsafe { to_string(info.type_) },
+                length,
+            });
+        }
+        Ok(leaves)
+    }
+
+    /// Run information stored in the file
+    ///
+    /// Fields that are not present in the file are left empty.
+    pub fn run_info(&self) -> Result<RunInfo, RunInfoError> {
+        unsafe { run_info::read(self.reader) }
+    }
+
+    /// Ranges of entries that could not be read and were skipped
+    ///
+    /// Always empty unless the reader was opened with
+    /// [OnError::Skip] or [OnError::Collect].
+    pub fn lost_entries(&self) -> &[Range<i64>] {
+        &self.lost
+    }
+
+    /// Number of entries that have not been read yet
+    ///
+    /// Unless the reader was opened with [OnError::Continue], this is
+    /// only an upper bound for the number of remaining events.
+    pub fn remaining_entries(&self) -> usize {
+        if self.stopped {
+            return 0;
+        }
+        let evs = unsafe { ntuple_num_events(self.reader) };
+        (evs - self.idx).max(0) as usize
+    }
+
+    /// Total number of entries that could not be read and were skipped
+    pub fn nlost(&self) -> usize {
+        self.lost.iter().map(|r| (r.end - r.start) as usize).sum()
+    }
+
+    /// Errors for the skipped entries when using [OnError::Collect]
+    pub fn errors(&self) -> &[ReadError] {
+        &self.errors
+    }
+
     pub fn nevent(&self) -> &i64 {
         &self.idx
     }
@@ -56,35 +274,51 @@ impl Reader {
     pub fn nevent_mut(&mut self) -> &mut i64 {
         &mut self.idx
     }
+
+    /// Iterate over groups of consecutive events with the same `id`
+    pub fn groups(self) -> Groups<Self> {
+        Groups::new(self)
+    }
+
+    /// Iterate over the events passing the given filter expression
+    ///
+    /// See the [filter](crate::filter) module for the syntax.
+    pub fn filter_expr(self, expr: &str) -> Result<Filtered<Self>, ParseError> {
+        let filter = Filter::parse(expr)?;
+        Ok(Filtered::new(self, filter))
+    }
 }
 
 impl Iterator for Reader {
     type Item = Result<Event, ReadError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        use self::ReadError::*;
-        let res = unsafe { ntuple_read_event(self.reader, self.idx) };
-        if res.status != NTupleReadStatus::READ_NO_ENTRY {
-            self.idx += 1;
+        if self.stopped {
+            return None;
         }
-        match res.status {
-            NTupleReadStatus::READ_OK => Some(Ok(res.event.into())),
-            NTupleReadStatus::READ_NO_ENTRY => None,
-            NTupleReadStatus::READ_ERROR => Some(Err(ReadError)),
-            NTupleReadStatus::READ_EXCEPTION => Some(Err(Exception)),
-            NTupleReadStatus::READ_TOO_MANY_PARTICLES => {
-                Some(Err(TooManyParticles(res.event.nparticle)))
-            }
-            NTupleReadStatus::READ_NEGATIVE_NUMBER_OF_PARTICLES => {
-                Some(Err(NegParticleNum(res.event.nparticle)))
-            }
-            NTupleReadStatus::READ_TOO_MANY_WEIGHTS => {
-                Some(Err(TooManyWeights(res.event.nuwgt)))
-            }
-            NTupleReadStatus::READ_NEGATIVE_NUMBER_OF_WEIGHTS => {
-                Some(Err(NegWeightNum(res.event.nuwgt)))
+        loop {
+            let res = self.read_next();
+            let Some(Err(err)) = res else {
+                if res.is_none() {
+                    self.report_lost();
+                }
+                return res;
+            };
+            match self.on_error {
+                OnError::Continue => return Some(Err(err)),
+                OnError::Stop => {
+                    self.stopped = true;
+                    return Some(Err(err));
+                }
+                OnError::Skip => {
+                    warn!("{err}: {}. Skipping entry.", err.kind);
+                    add_to_ranges(&mut self.lost, err.entry);
+                }
+                OnError::Collect => {
+                    add_to_ranges(&mut self.lost, err.entry);
+                    self.errors.push(err);
+                }
             }
-            _ => Some(Err(Unknown)),
         }
     }
 
@@ -94,9 +328,15 @@ impl Iterator for Reader {
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        let evs = unsafe { ntuple_num_events(self.reader) };
-        let remaining = (evs - self.idx) as usize;
-        (remaining, Some(remaining))
+        let remaining = self.remaining_entries();
+        match self.on_error {
+            // each entry yields exactly one item
+            OnError::Continue => (remaining, Some(remaining)),
+            // entries may be skipped or reading may stop early
+            OnError::Stop | OnError::Skip | OnError::Collect => {
+                (0, Some(remaining))
+            }
+        }
     }
 
     fn last(mut self) -> Option<Self::Item> {
@@ -110,7 +350,73 @@ impl Iterator for Reader {
     }
 }
 
-impl ExactSizeIterator for Reader {}
+impl Reader {
+    fn read_next(&mut self) -> Option<Result<Event, ReadError>> {
+        use self::ReadErrorKind::*;
+        let entry = self.idx;
+        let res = unsafe { ntuple_read_event(self.reader, entry) };
+        if res.status != NTupleReadStatus::READ_NO_ENTRY {
+            self.idx += 1;
+        }
+        let kind = match res.status {
+            NTupleReadStatus::READ_OK => match res.event.try_into() {
+                Ok(event) => return Some(Ok(event)),
+                Err(kind) => kind,
+            },
+            NTupleReadStatus::READ_NO_ENTRY => return None,
+            NTupleReadStatus::READ_ERROR => ReadError,
+            NTupleReadStatus::READ_EXCEPTION => Exception(last_error()),
+            NTupleReadStatus::READ_TOO_MANY_PARTICLES => {
+                TooManyParticles(res.event.nparticle)
+            }
+            NTupleReadStatus::READ_NEGATIVE_NUMBER_OF_PARTICLES => {
+                NegParticleNum(res.event.nparticle)
+            }
+            NTupleReadStatus::READ_TOO_MANY_WEIGHTS => {
+                TooManyWeights(res.event.nuwgt)
+            }
+            NTupleReadStatus::READ_NEGATIVE_NUMBER_OF_WEIGHTS => {
+                NegWeightNum(res.event.nuwgt)
+            }
+            _ => Unknown,
+        };
+        Some(Err(self::ReadError {
+            path: self.path.clone(),
+            entry,
+            kind,
+        }))
+    }
+
+    // Log a summary of the skipped entries once we reach the end
+    fn report_lost(&mut self) {
+        if self.reported || self.lost.is_empty() {
+            return;
+        }
+        self.reported = true;
+        let ranges = Vec::from_iter(
+            self.lost.iter().map(|r| format!("{}..{}", r.start, r.end)),
+        );
+        warn!(
+            "Lost {} entries in {:?}: {}",
+            self.nlost(),
+            self.path,
+            ranges.join(", ")
+        );
+    }
+}
+
+// Safety: `s` has to point to a valid nul-terminated string
+unsafe fn to_string(s: *const c_char) -> String {
+    unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned()
+}
+
+// Add an entry to a list of sorted ranges, merging adjacent ones
+fn add_to_ranges(ranges: &mut Vec<Range<i64>>, entry: i64) {
+    match ranges.last_mut() {
+        Some(last) if last.end == entry => last.end += 1,
+        _ => ranges.push(entry..entry + 1),
+    }
+}
 
 impl Drop for Reader {
     fn drop(&mut self) {
@@ -118,8 +424,46 @@ impl Drop for Reader {
     }
 }
 
+/// A leaf in the `TTree` of an ntuple file
+#[derive(Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Leaf {
+    /// Name of the branch the leaf belongs to
+    pub branch: String,
+    /// Name of the leaf
+    ///
+    /// This is usually the same as the branch name, but for example
+    /// the `me_wgt` branch has a leaf named `me_wtg`.
+    pub name: String,
+    /// ROOT type of the leaf, e.g. `Float_t`
+    pub leaf_type: String,
+    /// For arrays, the name of the leaf holding the length
+    pub length: Option<String>,
+}
+
+/// Error listing the leaves of a `TTree`
 #[derive(Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub enum ReadError {
+pub enum LeafError {
+    #[error("Failed to list leaves: {0}")]
+    List(String),
+    #[error("Failed to inspect leaf {0}: {1}")]
+    Inspect(i64, String),
+}
+
+/// Error reading an entry
+#[derive(Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[error("Failed to read entry {entry} from {path:?}")]
+pub struct ReadError {
+    /// Path of the file
+    pub path: PathBuf,
+    /// Index of the entry in the `TTree`
+    pub entry: i64,
+    /// What went wrong
+    #[source]
+    pub kind: ReadErrorKind,
+}
+
+#[derive(Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum ReadErrorKind {
     #[error("Too many particles in event")]
     TooManyParticles(i32),
     #[error("Too many weights in event")]
@@ -128,24 +472,53 @@ pub enum ReadError {
     NegParticleNum(i32),
     #[error("Number of user weights is negative: `{0}`")]
     NegWeightNum(i32),
+    #[error("Unrecognised event type `{}`", char::from(*.0))]
+    BadPart(u8),
     #[error("Read error")]
     ReadError,
-    #[error("Encountered an exception during reading")]
-    Exception,
+    #[error("Encountered an exception during reading: {0}")]
+    Exception(String),
 
     #[error("Unknown error")]
     Unknown,
 }
 
-#[derive(Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Debug, Error)]
 pub enum CreateError {
+    #[error("Path {0:?} contains a nul byte")]
+    NulByteInPath(PathBuf),
     #[error("Failed to open {0:?}")]
     Open(PathBuf),
+    #[error("Failed to read input")]
+    Io(#[source] io::Error),
     #[error("Failed to find a `TTree` named \"BHSntuples\"")]
     NoTTree,
-    #[error("Encountered an exception during creation")]
-    Exception,
+    #[error("Encountered an exception during creation: {0}")]
+    Exception(String),
 
     #[error("Unknown error")]
     Unknown,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lost_ranges() {
+        let mut ranges = Vec::new();
+        for entry in [3, 4, 5, 8, 10, 11] {
+            add_to_ranges(&mut ranges, entry);
+        }
+        assert_eq!(ranges, [3..6, 8..9, 10..12]);
+    }
+
+    #[test]
+    fn nul_byte() {
+        let err = Reader::new("nul\0byte.root").unwrap_err();
+        assert!(matches!(
+            err,
+            CreateError::NulByteInPath(path) if path == Path::new("nul\0byte.root")
+        ));
+    }
+}
@@ -0,0 +1,601 @@
+//! Negative weight reduction via cell resampling
+//!
+//! This implements the cell resampling method of
+//! [arXiv:2109.07851](https://arxiv.org/abs/2109.07851). Starting
+//! from a seed event with negative weight, a cell is formed from the
+//! seed and its nearest neighbours in phase space until the sum of
+//! weights in the cell is no longer negative. The weights in the
+//! cell are then redistributed as
+//! `w_i → |w_i| Σ_j w_j / Σ_j |w_j|`, which preserves the total
+//! weight of the cell and makes all weights in it non-negative.
+//! Observables are preserved up to the resolution given by the cell
+//! size.
+//!
+//! The distance between two events is the sum of the distances
+//! between their outgoing particles of the same type, minimised over
+//! all possible pairings. The distance between two momenta `p` and
+//! `q` is `sqrt(|p⃗ - q⃗|² + τ² (p_⊥ - q_⊥)²)`. Events with a different
+//! number of particles of any type are never in the same cell. To
+//! make real-emission events comparable with events at lower
+//! multiplicity, partons can be clustered into jets first.
+//!
+//! All other weights of an event are rescaled by the same factor as
+//! `weight`. Since cells are formed across all events, the events
+//! have to be kept in memory.
+//!
+//! Nearest neighbours are found with a vantage-point tree for each
+//! set of events with the same particle content. Neighbours are only
+//! examined until the cell is complete, so the cost is dominated by
+//! the distance computations needed to build the trees and to find
+//! the cells. Large cells, e.g. for seeds in sparsely populated
+//! regions of phase space, still require many distance computations.
+//! A finite [max_radius](ResampleOptions::max_radius) bounds this
+//! cost. For more than eight particles of the same type, the pairing
+//! of particles is only approximated. The search can then miss some
+//! of the nearest neighbours.
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    convert::Infallible,
+    path::Path,
+};
+
+use thiserror::Error;
+
+use crate::{
+    jets::{cluster, is_parton, JetDefinition},
+    reader::{self, ReadError},
+    storage::{EventSink, EventSource},
+    writer::{self, WriteError},
+    Event, FourMomentum, Reader, Writer,
+};
+
+// particle type for jets, 0 is not a valid PDG id
+const JET: i32 = 0;
+
+// largest number of particles of the same type for which the
+// optimal pairing is determined exactly
+const MAX_EXACT_MATCHING: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+pub struct ResampleOptions {
+    /// Weight `τ` of the transverse momentum difference in the distance
+    pub tau: f64,
+    /// Largest allowed distance between a seed and the events in its cell
+    ///
+    /// If a cell reaches this size without a non-negative sum of
+    /// weights, it is still resampled. All its weights then become
+    /// negative. Such cells are counted in
+    /// [nradius_limited](ResampleStats::nradius_limited).
+    ///
+    /// The default is infinite, so a cell grows until its sum of
+    /// weights is non-negative. Such a cell can span all of phase
+    /// space, and finding it takes a distance computation for each
+    /// event with the same particle content.
+    pub max_radius: f64,
+    /// Cluster partons into jets before computing distances
+    pub jets: Option<JetDefinition>,
+    /// Title of the `TTree` in the output file
+    pub title: String,
+}
+
+impl ResampleOptions {
+    pub fn new() -> Self {
+        Self {
+            tau: 0.,
+            max_radius: f64::INFINITY,
+            jets: None,
+            title: String::new(),
+        }
+    }
+}
+
+impl Default for ResampleOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Summary of a resampling run
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct ResampleStats {
+    /// Number of events
+    pub nevents: usize,
+    /// Number of resampled cells
+    pub ncells: usize,
+    /// Number of events with negative weight before resampling
+    pub nnegative_before: usize,
+    /// Number of events with negative weight after resampling
+    pub nnegative_after: usize,
+    /// Largest cell radius
+    pub max_cell_radius: f64,
+    /// Number of cells that reached the maximum radius with a negative
+    /// sum of weights
+    ///
+    /// If this is not zero, [max_radius](ResampleOptions::max_radius)
+    /// is too small to compensate all negative weights.
+    pub nradius_limited: usize,
+}
+
+/// Resample the events in an ntuple file
+pub fn resample<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    opts: &ResampleOptions,
+) -> Result<ResampleStats, ResampleError> {
+    let reader = Reader::new(input)?;
+    let mut writer = Writer::new(output, &opts.title)?;
+    let stats =
+        resample_into::<_, _, ResampleError>(reader, &mut writer, opts)?;
+    writer.finish()?;
+    Ok(stats)
+}
+
+/// Resample all events from `source` and write them to `sink`
+///
+/// The order of the events is preserved.
+pub fn resample_into<S, K, E>(
+    source: S,
+    sink: &mut K,
+    opts: &ResampleOptions,
+) -> Result<ResampleStats, E>
+where
+    S: EventSource,
+    K: EventSink + ?Sized,
+    E: From<S::Error> + From<K::Error>,
+{
+    let mut events = source.events().collect::<Result<Vec<_>, _>>()?;
+    let stats = resample_events(&mut events, opts);
+    for event in &events {
+        sink.write_event(event)?;
+    }
+    Ok(stats)
+}
+
+/// Resample events in place
+pub fn resample_events(
+    events: &mut [Event],
+    opts: &ResampleOptions,
+) -> ResampleStats {
+    let objects = Vec::from_iter(events.iter().map(|e| objects(e, opts)));
+    let dist =
+        |i: usize, j: usize| distance(&objects[i], &objects[j], opts.tau);
+    // events can only be in the same cell if they have the same
+    // particle content
+    let keys = Vec::from_iter(
+        objects
+            .iter()
+            .map(|obj| Vec::from_iter(obj.iter().map(|(t, p)| (*t, p.len())))),
+    );
+    let mut classes: HashMap<&[(i32, usize)], Vec<usize>> = HashMap::new();
+    for (idx, key) in keys.iter().enumerate() {
+        classes.entry(key).or_default().push(idx);
+    }
+    let trees: HashMap<_, _> = classes
+        .into_iter()
+        .map(|(key, class)| (key, VpTree::new(class, dist)))
+        .collect();
+
+    let mut stats = ResampleStats {
+        nevents: events.len(),
+        ..Default::default()
+    };
+    // start with the most negative weights
+    let mut seeds = Vec::from_iter(
+        (0..events.len()).filter(|&idx| events[idx].weight < 0.),
+    );
+    stats.nnegative_before = seeds.len();
+    seeds.sort_by(|&i, &j| events[i].weight.total_cmp(&events[j].weight));
+
+    for seed in seeds {
+        if events[seed].weight >= 0. {
+            // already resampled in an earlier cell
+            continue;
+        }
+        let neighbours = trees[keys[seed].as_slice()]
+            .nearest(|idx| dist(seed, idx))
+            .filter(|&(_, idx)| idx != seed);
+
+        let mut cell = vec![seed];
+        let mut sum = events[seed].weight;
+        let mut abs_sum = sum.abs();
+        let mut radius = 0f64;
+        for (dist, idx) in neighbours {
+            if sum >= 0. {
+                break;
+            }
+            if dist > opts.max_radius {
+                stats.nradius_limited += 1;
+                break;
+            }
+            cell.push(idx);
+            sum += events[idx].weight;
+            abs_sum += events[idx].weight.abs();
+            radius = dist;
+        }
+        for idx in cell {
+            let w = events[idx].weight;
+            if w != 0. {
+                events[idx].scale_weights(w.abs() * sum / (abs_sum * w));
+            }
+        }
+        stats.ncells += 1;
+        stats.max_cell_radius = stats.max_cell_radius.max(radius);
+    }
+    stats.nnegative_after = events.iter().filter(|e| e.weight < 0.).count();
+    stats
+}
+
+// momenta of the outgoing particles, grouped by type
+fn objects(
+    event: &Event,
+    opts: &ResampleOptions,
+) -> Vec<(i32, Vec<FourMomentum>)> {
+    let mut objects: Vec<(i32, Vec<FourMomentum>)> = Vec::new();
+    let mut add = |t: i32, p: FourMomentum| match objects
+        .iter_mut()
+        .find(|(ty, _)| *ty == t)
+    {
+        Some((_, momenta)) => momenta.push(p),
+        None => objects.push((t, vec![p])),
+    };
+    if let Some(def) = &opts.jets {
+        let partons =
+            event.particles().filter(|p| is_parton(p.pdg)).map(|p| p.p);
+        for jet in cluster(partons, def) {
+            add(JET, jet);
+        }
+        for p in event.particles().filter(|p| !is_parton(p.pdg)) {
+            add(p.pdg, p.p);
+        }
+    } else {
+        for p in event.particles() {
+            add(p.pdg, p.p);
+        }
+    }
+    objects.sort_by_key(|(t, _)| *t);
+    objects
+}
+
+fn distance(
+    e1: &[(i32, Vec<FourMomentum>)],
+    e2: &[(i32, Vec<FourMomentum>)],
+    tau: f64,
+) -> f64 {
+    debug_assert_eq!(e1.len(), e2.len());
+    e1.iter()
+        .zip(e2)
+        .map(|((_, p), (_, q))| matching_distance(p, q, tau))
+        .sum()
+}
+
+fn momentum_distance(p: &FourMomentum, q: &FourMomentum, tau: f64) -> f64 {
+    let dp = *p - *q;
+    let dpt = p.pt() - q.pt();
+    (dp.p2() + tau * tau * dpt * dpt).sqrt()
+}
+
+// smallest sum of distances over all pairings of `p` and `q`
+fn matching_distance(p: &[FourMomentum], q: &[FourMomentum], tau: f64) -> f64 {
+    debug_assert_eq!(p.len(), q.len());
+    let n = p.len();
+    let dist = |i: usize, j: usize| momentum_distance(&p[i], &q[j], tau);
+    if n > MAX_EXACT_MATCHING {
+        // greedily pair with the closest remaining momentum
+        let mut unpaired = Vec::from_iter(0..n);
+        let mut res = 0.;
+        for i in 0..n {
+            let (pos, d) = unpaired
+                .iter()
+                .map(|&j| dist(i, j))
+                .enumerate()
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            unpaired.swap_remove(pos);
+            res += d;
+        }
+        return res;
+    }
+    // `min[mask]` is the smallest distance when pairing the first
+    // `mask.count_ones()` momenta in `p` with those in `q` selected
+    // by `mask`
+    let mut min = vec![f64::INFINITY; 1 << n];
+    min[0] = 0.;
+    for mask in 0..(1usize << n) {
+        let i = mask.count_ones() as usize;
+        if i == n {
+            continue;
+        }
+        for j in (0..n).filter(|j| mask & (1 << j) == 0) {
+            let next = mask | (1 << j);
+            min[next] = min[next].min(min[mask] + dist(i, j));
+        }
+    }
+    min[(1 << n) - 1]
+}
+
+// Vantage-point tree over a set of events
+//
+// Each node splits the remaining events into those inside and
+// outside a sphere around the vantage point. With the triangle
+// inequality, the distances of all events in a subtree to any query
+// event are then bounded from below.
+#[derive(Clone, Debug)]
+struct VpTree {
+    // the root is the first node
+    nodes: Vec<VpNode>,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct VpNode {
+    point: usize,
+    // distance from the vantage point separating the subtrees
+    radius: f64,
+    inside: Option<usize>,
+    outside: Option<usize>,
+}
+
+impl VpTree {
+    fn new<D: Fn(usize, usize) -> f64>(
+        mut points: Vec<usize>,
+        dist: D,
+    ) -> Self {
+        let mut nodes = Vec::with_capacity(points.len());
+        Self::build(&mut points, &dist, &mut nodes);
+        Self { nodes }
+    }
+
+    // add a subtree with the given points, returning the index of its root
+    fn build<D: Fn(usize, usize) -> f64>(
+        points: &mut [usize],
+        dist: &D,
+        nodes: &mut Vec<VpNode>,
+    ) -> Option<usize> {
+        let (&mut point, rest) = points.split_first_mut()?;
+        let mut dists =
+            Vec::from_iter(rest.iter().map(|&p| (dist(point, p), p)));
+        let mid = dists.len() / 2;
+        let radius = if dists.is_empty() {
+            0.
+        } else {
+            dists.select_nth_unstable_by(mid, |a, b| a.0.total_cmp(&b.0));
+            dists[mid].0
+        };
+        for (p, (_, idx)) in rest.iter_mut().zip(dists) {
+            *p = idx;
+        }
+        let node = nodes.len();
+        nodes.push(VpNode {
+            point,
+            radius,
+            inside: None,
+            outside: None,
+        });
+        let (inside, outside) = rest.split_at_mut(mid);
+        nodes[node].inside = Self::build(inside, dist, nodes);
+        nodes[node].outside = Self::build(outside, dist, nodes);
+        Some(node)
+    }
+
+    // iterate over all points in order of increasing distance, where
+    // `dist` is the distance to the query point
+    fn nearest<D: Fn(usize) -> f64>(&self, dist: D) -> Nearest<'_, D> {
+        let mut queue = BinaryHeap::new();
+        if !self.nodes.is_empty() {
+            queue.push(Candidate {
+                dist: 0.,
+                kind: CandidateKind::Node(0),
+            });
+        }
+        Nearest {
+            tree: self,
+            dist,
+            queue,
+        }
+    }
+}
+
+// Best-first search through a vantage-point tree
+struct Nearest<'a, D> {
+    tree: &'a VpTree,
+    dist: D,
+    queue: BinaryHeap<Candidate>,
+}
+
+impl<D: Fn(usize) -> f64> Iterator for Nearest<'_, D> {
+    type Item = (f64, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(Candidate { dist: bound, kind }) = self.queue.pop() {
+            let node = match kind {
+                CandidateKind::Point(point) => return Some((bound, point)),
+                CandidateKind::Node(node) => self.tree.nodes[node],
+            };
+            let dist = (self.dist)(node.point);
+            self.queue.push(Candidate {
+                dist,
+                kind: CandidateKind::Point(node.point),
+            });
+            let subtrees = [
+                (node.inside, dist - node.radius),
+                (node.outside, node.radius - dist),
+            ];
+            for (subtree, sub_bound) in subtrees {
+                if let Some(subtree) = subtree {
+                    self.queue.push(Candidate {
+                        dist: bound.max(sub_bound),
+                        kind: CandidateKind::Node(subtree),
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+// Entry in the search queue, with the exact distance for points and a
+// lower bound for subtrees
+#[derive(Copy, Clone, Debug)]
+struct Candidate {
+    dist: f64,
+    kind: CandidateKind,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum CandidateKind {
+    Point(usize),
+    Node(usize),
+}
+
+// reversed, so that `BinaryHeap` returns the closest candidate first
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.dist.total_cmp(&self.dist)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+#[derive(Debug, Error)]
+pub enum ResampleError {
+    #[error(transparent)]
+    Open(#[from] reader::CreateError),
+    #[error(transparent)]
+    Create(#[from] writer::CreateError),
+    #[error(transparent)]
+    Read(#[from] ReadError),
+    #[error(transparent)]
+    Write(#[from] WriteError),
+}
+
+impl From<Infallible> for ResampleError {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(weight: f64, particles: &[(i32, f32)]) -> Event {
+        let n = particles.len();
+        Event {
+            nparticle: n as i32,
+            px: Vec::from_iter(particles.iter().map(|p| p.1)),
+            py: vec![0.; n],
+            pz: vec![0.; n],
+            energy: Vec::from_iter(particles.iter().map(|p| p.1.abs())),
+            pdg_code: Vec::from_iter(particles.iter().map(|p| p.0)),
+            weight,
+            me_weight: weight,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matching() {
+        let p = [
+            FourMomentum::new(1., 1., 0., 0.),
+            FourMomentum::new(2., 2., 0., 0.),
+        ];
+        let q = [p[1], p[0]];
+        assert_eq!(matching_distance(&p, &q, 0.), 0.);
+        let q = [FourMomentum::new(3., 3., 0., 0.), p[0]];
+        assert_eq!(matching_distance(&p, &q, 0.), 1.);
+        assert_eq!(matching_distance(&p, &q, 1.), 2f64.sqrt());
+    }
+
+    #[test]
+    fn nearest() {
+        // deterministic pseudo-random points on a line with duplicates
+        let points = Vec::from_iter((0..200).map(|i| ((i * 37) % 101) as f64));
+        let dist = |i: usize, j: usize| (points[i] - points[j]).abs();
+        let tree = VpTree::new(Vec::from_iter(0..points.len()), dist);
+        for query in [0, 17, 199] {
+            let found = Vec::from_iter(tree.nearest(|i| dist(query, i)));
+            assert_eq!(found.len(), points.len());
+            assert!(found.windows(2).all(|w| w[0].0 <= w[1].0));
+            assert!(found.iter().all(|&(d, i)| d == dist(query, i)));
+            let mut idx = Vec::from_iter(found.iter().map(|(_, i)| *i));
+            idx.sort();
+            assert!(idx.iter().copied().eq(0..points.len()));
+        }
+        assert_eq!(VpTree::new(Vec::new(), dist).nearest(|_| 0.).next(), None);
+    }
+
+    #[test]
+    fn max_radius() {
+        let mut events = vec![
+            event(-1., &[(11, 1.), (-11, -1.)]),
+            event(3., &[(11, 3.), (-11, -3.)]),
+        ];
+        let opts = ResampleOptions {
+            max_radius: 1.,
+            ..Default::default()
+        };
+        let stats = resample_events(&mut events, &opts);
+        assert_eq!(stats.ncells, 1);
+        assert_eq!(stats.max_cell_radius, 0.);
+        assert_eq!(stats.nradius_limited, 1);
+        assert_eq!(events[0].weight, -1.);
+        assert_eq!(events[1].weight, 3.);
+    }
+
+    #[test]
+    fn resample() {
+        let mut events = vec![
+            event(-1., &[(11, 1.), (-11, -1.)]),
+            event(3., &[(11, 1.1), (-11, -1.1)]),
+            event(1., &[(11, 5.), (-11, -5.)]),
+            // different particle content
+            event(-2., &[(13, 1.), (-13, -1.)]),
+            event(5., &[(11, 1.), (-11, -1.), (21, 1.)]),
+        ];
+        let xs: f64 = events.iter().map(|e| e.weight).sum();
+        let stats = resample_events(&mut events, &ResampleOptions::new());
+        assert_eq!(stats.nevents, 5);
+        assert_eq!(stats.ncells, 2);
+        assert_eq!(stats.nnegative_before, 2);
+        assert_eq!(stats.nnegative_after, 1);
+        // the remaining negative weight has no neighbours
+        assert_eq!(stats.nradius_limited, 0);
+        let new_xs: f64 = events.iter().map(|e| e.weight).sum();
+        assert!((new_xs - xs).abs() < 1e-12);
+        assert_eq!(events[0].weight, 0.5);
+        assert_eq!(events[1].weight, 1.5);
+        assert_eq!(events[1].me_weight, 1.5);
+        assert_eq!(events[2].weight, 1.);
+        assert_eq!(events[3].weight, -2.);
+        assert_eq!(events[4].weight, 5.);
+
+        // with jets, the collinear splitting doesn't prevent resampling
+        let mut events = vec![
+            event(-1., &[(11, 1.), (-11, -1.), (21, 0.1), (21, 0.1)]),
+            event(2., &[(11, 1.), (-11, -1.), (21, 0.2)]),
+        ];
+        resample_events(&mut events, &ResampleOptions::new());
+        assert_eq!(events[0].weight, -1.);
+        let opts = ResampleOptions {
+            jets: Some(JetDefinition {
+                algorithm: crate::jets::JetAlgorithm::AntiKt,
+                radius: 0.4,
+            }),
+            ..Default::default()
+        };
+        resample_events(&mut events, &opts);
+        assert!(events.iter().all(|e| e.weight > 0.));
+    }
+}
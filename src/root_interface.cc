@@ -1,5 +1,33 @@
+extern "C" {
+#include "create_error.h"
+}
+
+#include <string>
+
 #include "root_interface.hh"
 
 namespace ntuple {
   std::mutex file_mutex;
+
+  namespace {
+    thread_local std::string last_error;
+  }
+
+  void set_last_error(std::exception const & e) {
+    set_last_error(e.what());
+  }
+
+  void set_last_error(char const * msg) {
+    try {
+      last_error = msg;
+    } catch(...) {
+      // allocation failure, keep the old message
+    }
+  }
+}
+
+extern "C" {
+char const* ntuple_last_error() {
+  return ntuple::last_error.c_str();
+}
 }
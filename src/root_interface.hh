@@ -4,6 +4,7 @@
 #include <array>
 #include <cstddef>
 #include <stddef.h>
+#include <exception>
 #include <mutex>
 
 #include "RtypesCore.h"
@@ -20,6 +21,10 @@ namespace ntuple {
   // the current directory whenever we change data that might be written to file.
   extern std::mutex file_mutex;
 
+  // Remember the message of a caught exception for `ntuple_last_error`
+  void set_last_error(std::exception const & e);
+  void set_last_error(char const * msg);
+
   // the following is guaranteed by ROOT documentation,
   // so naturally we don't trust it
   static_assert(sizeof(Int_t) == sizeof(int32_t));
@@ -29,6 +34,9 @@ namespace ntuple {
   static_assert(sizeof(Float_t) == sizeof(float));
   static_assert(sizeof(Double_t) == sizeof(double));
 
+  // Directory for run information next to the `BHSntuples` tree
+  constexpr char const * RUN_INFO_DIR = "RunInfo";
+
   constexpr std::size_t MAX_NPARTICLE = 8192;
   constexpr std::size_t MAX_NWGT = 8192;
 
@@ -0,0 +1,187 @@
+//! Run information stored alongside the events
+//!
+//! The run information is kept in a `RunInfo` directory next to the
+//! `BHSntuples` tree. Strings are stored as `TNamed` objects with the
+//! value as title, numbers as `TParameter<double>` or
+//! `TParameter<Long64_t>`. Each field of [RunInfo] corresponds to one
+//! entry, named after the field. For the two beams, the entry names
+//! end in `1` and `2`, e.g. `beam_energy1`.
+use std::{
+    ffi::{c_char, CStr, CString},
+    ptr,
+};
+
+use thiserror::Error;
+
+use crate::bindings::{
+    last_error, ntuple_reader_get_double, ntuple_reader_get_int,
+    ntuple_reader_get_string, ntuple_writer_set_double, ntuple_writer_set_int,
+    ntuple_writer_set_string, NTupleReader, NTupleWriter,
+};
+
+/// Information about the run that produced the events
+///
+/// Fields that are not set are neither written nor found in the file.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RunInfo {
+    /// Energies of the two beams in GeV
+    pub beam_energy: Option<[f64; 2]>,
+    /// PDG ids of the two beam particles
+    pub beam_id: Option<[i32; 2]>,
+    /// Name of the generator
+    pub generator: Option<String>,
+    /// Version of the generator
+    pub generator_version: Option<String>,
+    /// Name of the PDF set
+    pub pdf_set: Option<String>,
+    /// Description of the process
+    pub process: Option<String>,
+    /// Total number of trials
+    pub ntrials: Option<u64>,
+}
+
+#[derive(Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum RunInfoError {
+    #[error("Value {1:?} for {0:?} contains a nul byte")]
+    NulByte(&'static str, String),
+    #[error("Invalid value for {0:?}")]
+    Invalid(&'static str),
+    #[error("Failed to access run information: {0}")]
+    Exception(String),
+}
+
+const BEAM_ENERGY: [&CStr; 2] = [c"beam_energy1", c"beam_energy2"];
+const BEAM_ID: [&CStr; 2] = [c"beam_id1", c"beam_id2"];
+const GENERATOR: &CStr = c"generator";
+const GENERATOR_VERSION: &CStr = c"generator_version";
+const PDF_SET: &CStr = c"pdf_set";
+const PROCESS: &CStr = c"process";
+const NTRIALS: &CStr = c"ntrials";
+
+// Safety: `writer` has to be a valid, unfinished writer
+pub(crate) unsafe fn write(
+    writer: *mut NTupleWriter,
+    info: &RunInfo,
+) -> Result<(), RunInfoError> {
+    let check = |res| {
+        if res == 0 {
+            Ok(())
+        } else {
+            Err(RunInfoError::Exception(last_error()))
+        }
+    };
+    let set_string = |key: &'static CStr, value: &Option<String>| {
+        let Some(value) = value else {
+            return Ok(());
+        };
+        let Ok(cvalue) = CString::new(value.as_str()) else {
+            return Err(RunInfoError::NulByte(name(key), value.to_owned()));
+        };
+        check(unsafe {
+            ntuple_writer_set_string(writer, key.as_ptr(), cvalue.as_ptr())
+        })
+    };
+    if let Some(energies) = info.beam_energy {
+        for (key, e) in BEAM_ENERGY.iter().zip(energies) {
+            check(unsafe {
+                ntuple_writer_set_double(writer, key.as_ptr(), e)
+            })?;
+        }
+    }
+    if let Some(ids) = info.beam_id {
+        for (key, id) in BEAM_ID.iter().zip(ids) {
+            check(unsafe {
+                ntuple_writer_set_int(writer, key.as_ptr(), id.into())
+            })?;
+        }
+    }
+    set_string(GENERATOR, &info.generator)?;
+    set_string(GENERATOR_VERSION, &info.generator_version)?;
+    set_string(PDF_SET, &info.pdf_set)?;
+    set_string(PROCESS, &info.process)?;
+    if let Some(ntrials) = info.ntrials {
+        let Ok(ntrials) = i64::try_from(ntrials) else {
+            return Err(RunInfoError::Invalid(name(NTRIALS)));
+        };
+        check(unsafe {
+            ntuple_writer_set_int(writer, NTRIALS.as_ptr(), ntrials)
+        })?;
+    }
+    Ok(())
+}
+
+// Safety: `reader` has to be a valid reader
+pub(crate) unsafe fn read(
+    reader: *mut NTupleReader,
+) -> Result<RunInfo, RunInfoError> {
+    let found = |res| match res {
+        1 => Ok(true),
+        0 => Ok(false),
+        _ => Err(RunInfoError::Exception(last_error())),
+    };
+    let get_string = |key: &CStr| {
+        let mut value: *const c_char = ptr::null();
+        let res = unsafe {
+            ntuple_reader_get_string(reader, key.as_ptr(), &mut value)
+        };
+        // the string is only valid until the next call, so copy it
+        Ok(found(res)?.then(|| {
+            let value = unsafe { CStr::from_ptr(value) };
+            value.to_string_lossy().into_owned()
+        }))
+    };
+    let get_double = |key: &CStr| {
+        let mut value = 0.;
+        let res = unsafe {
+            ntuple_reader_get_double(reader, key.as_ptr(), &mut value)
+        };
+        Ok(found(res)?.then_some(value))
+    };
+    let get_int = |key: &CStr| {
+        let mut value = 0;
+        let res =
+            unsafe { ntuple_reader_get_int(reader, key.as_ptr(), &mut value) };
+        Ok(found(res)?.then_some(value))
+    };
+
+    let beam_energy =
+        match [get_double(BEAM_ENERGY[0])?, get_double(BEAM_ENERGY[1])?] {
+            [Some(e1), Some(e2)] => Some([e1, e2]),
+            _ => None,
+        };
+    let mut beam_id = [None; 2];
+    for (id, key) in beam_id.iter_mut().zip(BEAM_ID) {
+        if let Some(value) = get_int(key)? {
+            let Ok(value) = i32::try_from(value) else {
+                return Err(RunInfoError::Invalid(name(key)));
+            };
+            *id = Some(value);
+        }
+    }
+    let beam_id = match beam_id {
+        [Some(id1), Some(id2)] => Some([id1, id2]),
+        _ => None,
+    };
+    let ntrials = match get_int(NTRIALS)? {
+        Some(n) => match u64::try_from(n) {
+            Ok(n) => Some(n),
+            Err(_) => return Err(RunInfoError::Invalid(name(NTRIALS))),
+        },
+        None => None,
+    };
+    Ok(RunInfo {
+        beam_energy,
+        beam_id,
+        generator: get_string(GENERATOR)?,
+        generator_version: get_string(GENERATOR_VERSION)?,
+        pdf_set: get_string(PDF_SET)?,
+        process: get_string(PROCESS)?,
+        ntrials,
+    })
+}
+
+fn name(key: &'static CStr) -> &'static str {
+    // all keys are ASCII
+    key.to_str().unwrap()
+}
@@ -0,0 +1,312 @@
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::{
+    group::Groups,
+    reader::{self, ReadError},
+    storage::{EventSink, EventSource},
+    writer::{self, WriteError},
+    Event, Reader, Writer,
+};
+
+/// How large the individual parts should be
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum SplitSize {
+    /// Split into the given number of parts of (roughly) equal size
+    Parts(usize),
+    /// Split into chunks with the given number of events each
+    Chunks(usize),
+}
+
+/// How events are distributed over the parts
+#[derive(Copy, Clone, Default, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Distribution {
+    /// Consecutive events end up in the same part
+    #[default]
+    Contiguous,
+    /// Events are assigned to the parts in turn
+    RoundRobin,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SplitOptions {
+    pub size: SplitSize,
+    pub distribution: Distribution,
+    /// Never separate consecutive events with the same `id`
+    ///
+    /// Real-emission events and their subtraction counter-events share
+    /// the same `id`. With this option, parts can be slightly larger
+    /// than requested.
+    pub keep_groups: bool,
+    /// Title of the `TTree`s in the output files
+    pub title: String,
+}
+
+impl SplitOptions {
+    pub fn new(size: SplitSize) -> Self {
+        Self {
+            size,
+            distribution: Default::default(),
+            keep_groups: false,
+            title: String::new(),
+        }
+    }
+}
+
+/// Split an ntuple file into several smaller files
+///
+/// The output files are named `<prefix>.<n>.root`, where `n` is the
+/// zero-padded index of the part starting from 0. All parts are
+/// created, even if some of them end up without any events. The
+/// paths of the output files are returned in order.
+pub fn split<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    prefix: Q,
+    opts: &SplitOptions,
+) -> Result<Vec<PathBuf>, SplitError> {
+    let reader = Reader::new(input)?;
+    let prefix = prefix.as_ref();
+    let nparts = split_into(reader, opts, |part, nparts| {
+        Writer::new(part_path(prefix, part, nparts), &opts.title)
+            .map_err(SplitError::from)
+    })?;
+    Ok(Vec::from_iter(
+        (0..nparts).map(|n| part_path(prefix, n, nparts)),
+    ))
+}
+
+/// Split the events from a source into several sinks
+///
+/// `create_part` is called with the index of each part and the total
+/// number of parts to create the corresponding sink. Sinks are
+/// created in order and all parts are created, even if some of them
+/// end up without any events. The number of parts is returned.
+///
+/// The source has to know the number of events it contains. If it
+/// only knows an upper bound, for example because a [Reader] skips
+/// entries that cannot be read, the parts can end up with fewer
+/// events than requested.
+pub fn split_into<S, K, F, E>(
+    source: S,
+    opts: &SplitOptions,
+    mut create_part: F,
+) -> Result<usize, E>
+where
+    S: EventSource,
+    K: EventSink,
+    F: FnMut(usize, usize) -> Result<K, E>,
+    E: From<S::Error> + From<K::Error> + From<SplitError>,
+{
+    let Some(nevents) = source.remaining() else {
+        return Err(SplitError::UnknownSize.into());
+    };
+    let nparts = match opts.size {
+        SplitSize::Parts(0) | SplitSize::Chunks(0) => {
+            return Err(SplitError::ZeroSize.into())
+        }
+        SplitSize::Parts(n) => n,
+        SplitSize::Chunks(n) => nevents.div_ceil(n),
+    };
+    // number of events after which we switch to the next part
+    // when splitting contiguously
+    let part_end = |part: usize| match opts.size {
+        SplitSize::Parts(n) => ((part + 1) * nevents).div_ceil(n),
+        SplitSize::Chunks(n) => (part + 1) * n,
+    };
+
+    let mut sinks: Vec<Option<K>> = Vec::from_iter((0..nparts).map(|_| None));
+    let mut ncreated = 0;
+    let mut part = 0;
+    let mut nwritten = 0;
+    let events = source.events();
+    let units: Box<dyn Iterator<Item = Result<Vec<Event>, S::Error>>> =
+        if opts.keep_groups {
+            Box::new(Groups::new(events))
+        } else {
+            Box::new(events.map(|event| event.map(|event| vec![event])))
+        };
+    for (nunit, unit) in units.enumerate() {
+        let unit = unit?;
+        match opts.distribution {
+            Distribution::Contiguous => {
+                while nwritten >= part_end(part) && part + 1 < nparts {
+                    // close the finished part
+                    if let Some(sink) = sinks[part].take() {
+                        sink.finish()?;
+                    }
+                    part += 1;
+                }
+            }
+            Distribution::RoundRobin => part = nunit % nparts,
+        }
+        while ncreated <= part {
+            sinks[ncreated] = Some(create_part(ncreated, nparts)?);
+            ncreated += 1;
+        }
+        let sink = sinks[part].as_mut().unwrap();
+        for event in &unit {
+            sink.write_event(event)?;
+        }
+        nwritten += unit.len();
+    }
+    for sink in sinks.into_iter().flatten() {
+        sink.finish()?;
+    }
+    // create remaining empty parts
+    for part in ncreated..nparts {
+        create_part(part, nparts)?.finish()?;
+    }
+    Ok(nparts)
+}
+
+/// Path of the output file for the given part
+pub fn part_path(prefix: &Path, part: usize, nparts: usize) -> PathBuf {
+    let width = nparts.saturating_sub(1).to_string().len();
+    let mut path = prefix.as_os_str().to_owned();
+    path.push(format!(".{part:0width$}.root"));
+    PathBuf::from(path)
+}
+
+#[derive(Debug, Error)]
+pub enum SplitError {
+    #[error("Number of parts and chunk size have to be positive")]
+    ZeroSize,
+    #[error("Number of events in source is unknown")]
+    UnknownSize,
+    #[error(transparent)]
+    Open(#[from] reader::CreateError),
+    #[error(transparent)]
+    Create(#[from] writer::CreateError),
+    #[error(transparent)]
+    Read(#[from] ReadError),
+    #[error(transparent)]
+    Write(#[from] WriteError),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, convert::Infallible};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Error;
+
+    impl From<Infallible> for Error {
+        fn from(err: Infallible) -> Self {
+            match err {}
+        }
+    }
+
+    impl From<SplitError> for Error {
+        fn from(_: SplitError) -> Self {
+            Error
+        }
+    }
+
+    // sink recording the ids of the events in the given part
+    struct PartSink<'a> {
+        parts: &'a RefCell<Vec<Vec<i32>>>,
+        part: usize,
+    }
+
+    impl EventSink for PartSink<'_> {
+        type Error = Infallible;
+
+        fn write_event(&mut self, event: &Event) -> Result<(), Infallible> {
+            self.parts.borrow_mut()[self.part].push(event.id);
+            Ok(())
+        }
+    }
+
+    fn split_ids(ids: &[i32], opts: &SplitOptions) -> Vec<Vec<i32>> {
+        let events = Vec::from_iter(ids.iter().map(|&id| Event {
+            id,
+            ..Default::default()
+        }));
+        let parts = RefCell::new(Vec::new());
+        let nparts = split_into(events.into_iter(), opts, |part, _| {
+            parts.borrow_mut().push(Vec::new());
+            Ok::<_, Error>(PartSink {
+                parts: &parts,
+                part,
+            })
+        })
+        .unwrap();
+        let parts = parts.into_inner();
+        assert_eq!(nparts, parts.len());
+        parts
+    }
+
+    #[test]
+    fn split_events() {
+        let ids = [1, 1, 2, 3, 3, 3, 4];
+        let opts = SplitOptions::new(SplitSize::Parts(3));
+        assert_eq!(split_ids(&ids, &opts), [&ids[..3], &ids[3..5], &ids[5..]]);
+
+        let opts = SplitOptions {
+            keep_groups: true,
+            ..opts
+        };
+        assert_eq!(split_ids(&ids, &opts), [&ids[..3], &ids[3..6], &ids[6..]]);
+
+        let opts = SplitOptions {
+            distribution: Distribution::RoundRobin,
+            ..SplitOptions::new(SplitSize::Chunks(3))
+        };
+        assert_eq!(
+            split_ids(&ids, &opts),
+            [vec![1, 3, 4], vec![1, 3], vec![2, 3]]
+        );
+
+        let opts = SplitOptions {
+            keep_groups: true,
+            ..opts
+        };
+        assert_eq!(
+            split_ids(&ids, &opts),
+            [vec![1, 1, 4], vec![2], vec![3, 3, 3]]
+        );
+
+        let opts = SplitOptions::new(SplitSize::Parts(3));
+        // leading parts are filled first
+        assert_eq!(split_ids(&[1], &opts), [vec![1], vec![], vec![]]);
+    }
+
+    #[test]
+    fn finish_parts() {
+        // sink recording which parts have been finished
+        struct FinishSink<'a> {
+            finished: &'a RefCell<Vec<usize>>,
+            part: usize,
+        }
+
+        impl EventSink for FinishSink<'_> {
+            type Error = Infallible;
+
+            fn write_event(&mut self, _: &Event) -> Result<(), Infallible> {
+                Ok(())
+            }
+
+            fn finish(self) -> Result<(), Infallible> {
+                self.finished.borrow_mut().push(self.part);
+                Ok(())
+            }
+        }
+
+        let events = vec![Event::default(); 4];
+        let finished = RefCell::new(Vec::new());
+        let opts = SplitOptions::new(SplitSize::Parts(6));
+        split_into(events.into_iter(), &opts, |part, _| {
+            Ok::<_, Error>(FinishSink {
+                finished: &finished,
+                part,
+            })
+        })
+        .unwrap();
+        let mut finished = finished.into_inner();
+        finished.sort();
+        assert_eq!(finished, [0, 1, 2, 3, 4, 5]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Storage-agnostic event input and output
+//!
+//! [EventSource] and [EventSink] abstract over the different places
+//! events can be read from and written to, e.g. ntuple files via
+//! [Reader] and [Writer], HepMC streams, or vectors of events in
+//! memory. Generic tools only have to be written once and can be
+//! tested without ROOT.
+use std::convert::Infallible;
+
+use crate::{reader::ReadError, writer::WriteError, Event, Reader, Writer};
+
+/// Source of events
+pub trait EventSource {
+    type Error;
+
+    /// Read the next event
+    ///
+    /// Returns `None` if there are no events left.
+    fn read_event(&mut self) -> Option<Result<Event, Self::Error>>;
+
+    /// Number of remaining events, if known
+    ///
+    /// Sources that may skip entries, e.g. a [Reader] opened with
+    /// [OnError::Skip](crate::reader::OnError::Skip), return an upper
+    /// bound.
+    fn remaining(&self) -> Option<usize> {
+        None
+    }
+
+    /// Turn the source into an iterator over events
+    fn events(self) -> Events<Self>
+    where
+        Self: Sized,
+    {
+        Events(self)
+    }
+}
+
+/// Destination for events
+pub trait EventSink {
+    type Error;
+
+    fn write_event(&mut self, event: &Event) -> Result<(), Self::Error>;
+
+    /// Write all remaining data and close the sink
+    ///
+    /// Errors from sinks that are dropped instead are lost. The
+    /// default implementation does nothing.
+    fn finish(self) -> Result<(), Self::Error>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// Iterator over the events in an [EventSource]
+#[derive(Clone, Debug)]
+pub struct Events<S>(S);
+
+impl<S> Events<S> {
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S: EventSource> Iterator for Events<S> {
+    type Item = Result<Event, S::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.read_event()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.0.remaining() {
+            Some(n) => (0, Some(n)),
+            None => (0, None),
+        }
+    }
+}
+
+/// Copy all events from `source` to `sink`
+///
+/// Returns the number of copied events.
+pub fn copy<S, K, E>(source: &mut S, sink: &mut K) -> Result<usize, E>
+where
+    S: EventSource + ?Sized,
+    K: EventSink + ?Sized,
+    E: From<S::Error> + From<K::Error>,
+{
+    let mut ncopied = 0;
+    while let Some(event) = source.read_event() {
+        sink.write_event(&event?)?;
+        ncopied += 1;
+    }
+    Ok(ncopied)
+}
+
+impl<S: EventSource + ?Sized> EventSource for &mut S {
+    type Error = S::Error;
+
+    fn read_event(&mut self) -> Option<Result<Event, Self::Error>> {
+        (**self).read_event()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        (**self).remaining()
+    }
+}
+
+impl<K: EventSink + ?Sized> EventSink for &mut K {
+    type Error = K::Error;
+
+    fn write_event(&mut self, event: &Event) -> Result<(), Self::Error> {
+        (**self).write_event(event)
+    }
+}
+
+impl EventSource for Reader {
+    type Error = ReadError;
+
+    fn read_event(&mut self) -> Option<Result<Event, Self::Error>> {
+        self.next()
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        Some(self.remaining_entries())
+    }
+}
+
+impl EventSink for Writer {
+    type Error = WriteError;
+
+    fn write_event(&mut self, event: &Event) -> Result<(), Self::Error> {
+        self.write(event)
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        Writer::finish(self)
+    }
+}
+
+impl EventSource for std::vec::IntoIter<Event> {
+    type Error = Infallible;
+
+    fn read_event(&mut self) -> Option<Result<Event, Self::Error>> {
+        self.next().map(Ok)
+    }
+
+    fn remaining(&self) -> Option<usize> {
+        Some(self.len())
+    }
+}
+
+impl EventSink for Vec<Event> {
+    type Error = Infallible;
+
+    fn write_event(&mut self, event: &Event) -> Result<(), Self::Error> {
+        self.push(event.clone());
+        Ok(())
+    }
+}
+
+#[cfg(feature = "hepmc2")]
+impl<T: std::io::BufRead> EventSource for hepmc2::Reader<T> {
+    type Error = hepmc2::reader::LineParseError;
+
+    fn read_event(&mut self) -> Option<Result<Event, Self::Error>> {
+        self.next()
+            .map(|event| event.map(|event| Event::from(&event)))
+    }
+}
+
+#[cfg(feature = "hepmc2")]
+impl<T: std::io::Write> EventSink for hepmc2::Writer<T> {
+    type Error = std::io::Error;
+
+    fn write_event(&mut self, event: &Event) -> Result<(), Self::Error> {
+        self.write(&hepmc2::Event::from(event))
+    }
+
+    fn finish(self) -> Result<(), Self::Error> {
+        hepmc2::Writer::finish(self)
+    }
+}
@@ -0,0 +1,367 @@
+//! Unweighting of weighted events
+//!
+//! Unweighting is done in two passes. First, [find_cap] determines
+//! the reference weight from the absolute event weights. Then,
+//! [unweight_into] performs hit-or-miss unweighting: an event with
+//! weight `w` is accepted with probability `|w| / cap`. Accepted
+//! events get the weight `±cap`, keeping the sign of the original
+//! weight, so that the sum of weights still estimates the cross
+//! section. All other weights are rescaled by the same factor.
+//!
+//! Events with `|w| > cap` are always accepted and handled according
+//! to [Overweight]. Events with vanishing weight are always rejected.
+//! Each event is treated independently, so the correlation between
+//! events with the same `id` is lost.
+use std::{convert::Infallible, path::Path};
+
+use rand::{RngExt, SeedableRng};
+use rand_pcg::Pcg64Mcg;
+use thiserror::Error;
+
+use crate::{
+    reader::{self, ReadError},
+    storage::{EventSink, EventSource},
+    writer::{self, WriteError},
+    Reader, Writer,
+};
+
+/// How to choose the reference weight
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub enum Cap {
+    /// Largest absolute weight
+    #[default]
+    Max,
+    /// Quantile of the absolute weights
+    ///
+    /// For example, with `Quantile(0.99)` one percent of the events
+    /// with non-vanishing weight are overweight. Events are then
+    /// accepted with a higher probability, at the cost of a few
+    /// events that do not have unit weight.
+    Quantile(f64),
+    /// Fixed reference weight
+    Fixed(f64),
+}
+
+/// What to do with events where the absolute weight exceeds the cap
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Overweight {
+    /// Keep the original weight
+    ///
+    /// This preserves the cross section, but the output is not
+    /// strictly unweighted.
+    #[default]
+    Keep,
+    /// Set the weight to `±cap`
+    ///
+    /// All output events have the same absolute weight, but the
+    /// cross section is underestimated.
+    Truncate,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct UnweightOptions {
+    pub cap: Cap,
+    pub overweight: Overweight,
+    /// Seed for the random number generator
+    pub seed: u64,
+    /// Title of the `TTree` in the output file
+    pub title: String,
+}
+
+impl UnweightOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Summary of an unweighting run
+#[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct UnweightStats {
+    /// Reference weight
+    pub cap: f64,
+    /// Number of input events
+    pub nevents: usize,
+    /// Number of accepted events
+    pub naccepted: usize,
+    /// Number of accepted events with negative weight
+    pub nnegative: usize,
+    /// Number of events with absolute weight above the cap
+    pub noverweight: usize,
+}
+
+impl UnweightStats {
+    /// Fraction of accepted events
+    ///
+    /// This is zero if there were no input events.
+    pub fn efficiency(&self) -> f64 {
+        if self.nevents == 0 {
+            return 0.;
+        }
+        self.naccepted as f64 / self.nevents as f64
+    }
+}
+
+/// Unweight the events in an ntuple file
+///
+/// The input file is read twice, first to determine the cap and then
+/// to select the events that are written to `output`.
+pub fn unweight<P: AsRef<Path>, Q: AsRef<Path>>(
+    input: P,
+    output: Q,
+    opts: &UnweightOptions,
+) -> Result<UnweightStats, UnweightError> {
+    let input = input.as_ref();
+    let cap = find_cap::<_, UnweightError>(Reader::new(input)?, opts.cap)?;
+    let reader = Reader::new(input)?;
+    let mut writer = Writer::new(output, &opts.title)?;
+    let stats =
+        unweight_into::<_, _, UnweightError>(reader, &mut writer, cap, opts)?;
+    writer.finish()?;
+    Ok(stats)
+}
+
+/// Determine the reference weight for the events in `source`
+pub fn find_cap<S, E>(source: S, cap: Cap) -> Result<f64, E>
+where
+    S: EventSource,
+    E: From<S::Error> + From<UnweightError>,
+{
+    let cap = match cap {
+        Cap::Fixed(cap) => cap,
+        Cap::Max => {
+            let mut max = 0f64;
+            for event in source.events() {
+                max = max.max(event?.weight.abs());
+            }
+            max
+        }
+        Cap::Quantile(q) => {
+            if !(q > 0. && q <= 1.) {
+                return Err(UnweightError::BadQuantile(q).into());
+            }
+            let mut weights = Vec::new();
+            for event in source.events() {
+                let w = event?.weight.abs();
+                if w > 0. {
+                    weights.push(w);
+                }
+            }
+            if weights.is_empty() {
+                return Err(UnweightError::BadCap(0.).into());
+            }
+            let idx = ((q * weights.len() as f64).ceil() as usize)
+                .clamp(1, weights.len())
+                - 1;
+            let (_, cap, _) =
+                weights.select_nth_unstable_by(idx, f64::total_cmp);
+            *cap
+        }
+    };
+    Ok(check_cap(cap)?)
+}
+
+fn check_cap(cap: f64) -> Result<f64, UnweightError> {
+    if cap > 0. && cap.is_finite() {
+        Ok(cap)
+    } else {
+        Err(UnweightError::BadCap(cap))
+    }
+}
+
+/// Unweight the events from `source` with the given reference weight
+///
+/// Accepted events are written to `sink`. The `cap` field of `opts`
+/// is ignored.
+pub fn unweight_into<S, K, E>(
+    source: S,
+    sink: &mut K,
+    cap: f64,
+    opts: &UnweightOptions,
+) -> Result<UnweightStats, E>
+where
+    S: EventSource,
+    K: EventSink + ?Sized,
+    E: From<S::Error> + From<K::Error> + From<UnweightError>,
+{
+    let cap = check_cap(cap)?;
+    let mut rng = Pcg64Mcg::seed_from_u64(opts.seed);
+    let mut stats = UnweightStats {
+        cap,
+        ..Default::default()
+    };
+    for event in source.events() {
+        let mut event = event?;
+        stats.nevents += 1;
+        let w = event.weight;
+        let abs_w = w.abs();
+        if abs_w > cap {
+            stats.noverweight += 1;
+            if opts.overweight == Overweight::Truncate {
+                event.scale_weights(cap / abs_w);
+            }
+        } else if abs_w > 0. && rng.random::<f64>() * cap < abs_w {
+            event.scale_weights(cap / abs_w);
+        } else {
+            continue;
+        }
+        stats.naccepted += 1;
+        if w < 0. {
+            stats.nnegative += 1;
+        }
+        sink.write_event(&event)?;
+    }
+    Ok(stats)
+}
+
+#[derive(Debug, Error)]
+pub enum UnweightError {
+    #[error("Quantile has to be in (0, 1], got {0}")]
+    BadQuantile(f64),
+    #[error("Reference weight has to be positive and finite, got {0}")]
+    BadCap(f64),
+    #[error(transparent)]
+    Open(#[from] reader::CreateError),
+    #[error(transparent)]
+    Create(#[from] writer::CreateError),
+    #[error(transparent)]
+    Read(#[from] ReadError),
+    #[error(transparent)]
+    Write(#[from] WriteError),
+}
+
+impl From<Infallible> for UnweightError {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Event;
+
+    fn events(weights: &[f64]) -> Vec<Event> {
+        Vec::from_iter(weights.iter().enumerate().map(|(id, &weight)| Event {
+            id: id as i32,
+            weight,
+            me_weight: 2. * weight,
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn cap() {
+        let ev = events(&[1., -4., 0., 2., 3.]);
+        let cap =
+            |cap| find_cap::<_, UnweightError>(ev.clone().into_iter(), cap);
+        assert_eq!(cap(Cap::Max).unwrap(), 4.);
+        assert_eq!(cap(Cap::Quantile(1.)).unwrap(), 4.);
+        assert_eq!(cap(Cap::Quantile(0.75)).unwrap(), 3.);
+        assert_eq!(cap(Cap::Quantile(0.1)).unwrap(), 1.);
+        assert_eq!(cap(Cap::Fixed(2.)).unwrap(), 2.);
+        assert!(matches!(
+            cap(Cap::Quantile(0.)),
+            Err(UnweightError::BadQuantile(0.))
+        ));
+        assert!(matches!(
+            find_cap::<_, UnweightError>(events(&[0.]).into_iter(), Cap::Max),
+            Err(UnweightError::BadCap(0.))
+        ));
+    }
+
+    #[test]
+    fn unweight_events() {
+        let weights = Vec::from_iter((0..10000).map(|i| {
+            let w = (i % 10) as f64 / 10.;
+            if i % 4 == 0 {
+                -w
+            } else {
+                w
+            }
+        }));
+        let xs: f64 = weights.iter().sum();
+        let opts = UnweightOptions {
+            seed: 42,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let stats = unweight_into::<_, _, UnweightError>(
+            events(&weights).into_iter(),
+            &mut out,
+            1.,
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(stats.nevents, weights.len());
+        assert_eq!(stats.naccepted, out.len());
+        assert_eq!(stats.noverweight, 0);
+        assert!(out.iter().all(|e| e.weight.abs() == 1.));
+        assert!(out.iter().all(|e| e.me_weight == 2. * e.weight));
+        assert_eq!(
+            stats.nnegative,
+            out.iter().filter(|e| e.weight < 0.).count()
+        );
+        let unweighted_xs: f64 = out.iter().map(|e| e.weight).sum();
+        // expect sqrt(N) fluctuations
+        assert!(
+            (unweighted_xs - xs).abs() < 3. * (stats.naccepted as f64).sqrt()
+        );
+
+        // same seed, same result
+        let mut out2 = Vec::new();
+        unweight_into::<_, _, UnweightError>(
+            events(&weights).into_iter(),
+            &mut out2,
+            1.,
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(out, out2);
+    }
+
+    #[test]
+    fn empty() {
+        let mut out = Vec::new();
+        let stats = unweight_into::<_, _, UnweightError>(
+            events(&[]).into_iter(),
+            &mut out,
+            1.,
+            &UnweightOptions::new(),
+        )
+        .unwrap();
+        assert_eq!(stats.nevents, 0);
+        assert_eq!(stats.efficiency(), 0.);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn overweight() {
+        let ev = events(&[-3., 0.5]);
+        let mut out = Vec::new();
+        let opts = UnweightOptions::new();
+        let stats = unweight_into::<_, _, UnweightError>(
+            ev.clone().into_iter(),
+            &mut out,
+            1.,
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(stats.noverweight, 1);
+        assert_eq!(out[0].weight, -3.);
+
+        let opts = UnweightOptions {
+            overweight: Overweight::Truncate,
+            ..opts
+        };
+        let mut out = Vec::new();
+        unweight_into::<_, _, UnweightError>(
+            ev.into_iter(),
+            &mut out,
+            1.,
+            &opts,
+        )
+        .unwrap();
+        assert_eq!(out[0].weight, -1.);
+        assert_eq!(out[0].me_weight, -2.);
+    }
+}
@@ -0,0 +1,187 @@
+use thiserror::Error;
+
+use crate::{event::Part, Event, FourMomentum};
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct ValidationOptions {
+    /// Energies of the two colliding beams
+    ///
+    /// Momentum conservation is only checked if these are set.
+    pub beam_energies: Option<[f64; 2]>,
+    /// Relative tolerance for momentum conservation
+    pub tolerance: f64,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            beam_energies: None,
+            tolerance: 1e-4,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[non_exhaustive]
+pub enum ValidationIssue {
+    #[error("Number of particles is negative: `{0}`")]
+    NegParticleNum(i32),
+    #[error("Length `{len}` of `{field}` does not match number of particles `{nparticle}`")]
+    LengthMismatch {
+        field: &'static str,
+        len: usize,
+        nparticle: i32,
+    },
+    #[error("`{0}` is not finite")]
+    NonFinite(&'static str),
+    #[error("Momentum of particle {0} is not finite")]
+    NonFiniteMomentum(usize),
+    #[error("Energy of particle {0} is negative")]
+    NegativeEnergy(usize),
+    #[error("`{0}` is not in the interval (0, 1]")]
+    MomentumFractionOutOfRange(&'static str),
+    #[error("Component {0} of the momentum is not conserved")]
+    MomentumNotConserved(usize),
+    #[error("`{field}` should vanish for event type '{part}'")]
+    UnexpectedValue { field: &'static str, part: char },
+}
+
+impl Event {
+    /// Check the event for inconsistencies
+    ///
+    /// An empty vector is returned for valid events.
+    pub fn validate(&self, opts: &ValidationOptions) -> Vec<ValidationIssue> {
+        use ValidationIssue::*;
+
+        let mut issues = Vec::new();
+        if self.nparticle < 0 {
+            issues.push(NegParticleNum(self.nparticle));
+        }
+        let particle_data = [
+            ("px", self.px.len()),
+            ("py", self.py.len()),
+            ("pz", self.pz.len()),
+            ("energy", self.energy.len()),
+            ("pdg_code", self.pdg_code.len()),
+        ];
+        let mut consistent_len = self.nparticle >= 0;
+        for (field, len) in particle_data {
+            if len != self.nparticle as usize {
+                consistent_len = false;
+                issues.push(LengthMismatch {
+                    field,
+                    len,
+                    nparticle: self.nparticle,
+                });
+            }
+        }
+
+        let scalars = [
+            ("alphas", self.alphas),
+            ("weight", self.weight),
+            ("weight2", self.weight2),
+            ("me_weight", self.me_weight),
+            ("me_weight2", self.me_weight2),
+            ("x1", self.x1),
+            ("x2", self.x2),
+            ("x1p", self.x1p),
+            ("x2p", self.x2p),
+            ("fac_scale", self.fac_scale),
+            ("ren_scale", self.ren_scale),
+        ];
+        for (field, val) in scalars {
+            if !val.is_finite() {
+                issues.push(NonFinite(field));
+            }
+        }
+        if self.user_weights.iter().any(|w| !w.is_finite()) {
+            issues.push(NonFinite("user_weights"));
+        }
+        for (field, x) in [("x1", self.x1), ("x2", self.x2)] {
+            if x.is_finite() && !(0. < x && x <= 1.) {
+                issues.push(MomentumFractionOutOfRange(field));
+            }
+        }
+
+        let part = char::from(self.part);
+        if self.part != Part::I {
+            for (field, x) in [("x1p", self.x1p), ("x2p", self.x2p)] {
+                if x != 0. {
+                    issues.push(UnexpectedValue { field, part });
+                }
+            }
+        }
+        if self.part != Part::V && self.me_weight2 != 0. {
+            issues.push(UnexpectedValue {
+                field: "me_weight2",
+                part,
+            });
+        }
+
+        if !consistent_len {
+            return issues;
+        }
+        let mut finite_momenta = true;
+        for (i, p) in self.particles().enumerate() {
+            if p.p.0.iter().any(|p| !p.is_finite()) {
+                finite_momenta = false;
+                issues.push(NonFiniteMomentum(i));
+            } else if p.p.e() < 0. {
+                issues.push(NegativeEnergy(i));
+            }
+        }
+        if let (true, Some([e1, e2])) = (finite_momenta, opts.beam_energies) {
+            let p1 = self.x1 * e1;
+            let p2 = self.x2 * e2;
+            let incoming = FourMomentum::new(p1 + p2, 0., 0., p1 - p2);
+            let outgoing = self.total_momentum();
+            let max_diff = opts.tolerance * incoming.e().abs();
+            for i in 0..4 {
+                if (outgoing[i] - incoming[i]).abs() > max_diff {
+                    issues.push(MomentumNotConserved(i));
+                }
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        let opts = ValidationOptions {
+            beam_energies: Some([100., 100.]),
+            ..Default::default()
+        };
+        let mut event = Event {
+            nparticle: 2,
+            px: vec![10., -10.],
+            py: vec![0., 0.],
+            pz: vec![20., -10.],
+            energy: vec![30., 20.],
+            pdg_code: vec![21, 21],
+            x1: 0.3,
+            x2: 0.2,
+            ..Default::default()
+        };
+        assert_eq!(event.validate(&opts), []);
+
+        event.x2 = 0.;
+        event.energy[1] = f32::NAN;
+        event.x1p = 0.5;
+        assert_eq!(
+            event.validate(&opts),
+            [
+                ValidationIssue::MomentumFractionOutOfRange("x2"),
+                ValidationIssue::UnexpectedValue {
+                    field: "x1p",
+                    part: 'B'
+                },
+                ValidationIssue::NonFiniteMomentum(1),
+            ]
+        );
+    }
+}
@@ -1,21 +1,41 @@
 use std::{
     ffi::CString,
+    fmt,
+    io::{self, Write},
     os::{raw::c_char, unix::prelude::OsStrExt},
     path::{Path, PathBuf},
+    ptr, slice,
 };
 
+use log::warn;
+
 use crate::{
     bindings::{
-        ntuple_create_writer, ntuple_delete_writer, ntuple_write_event,
-        NTupleCreateError, NTupleEvent, NTupleWriteResult,
-        NTupleWriterCreateResult,
+        last_error, ntuple_create_memory_writer, ntuple_create_writer,
+        ntuple_delete_writer, ntuple_finish_writer, ntuple_write_event,
+        ntuple_writer_buffer, NTupleCreateError, NTupleEvent,
+        NTupleWriteResult, NTupleWriterCreateResult,
     },
+    run_info::{self, RunInfo, RunInfoError},
+    validate::{ValidationIssue, ValidationOptions},
     Event,
 };
 use thiserror::Error;
 
-#[derive(Debug)]
-pub struct Writer(*mut crate::bindings::NTupleWriter);
+pub struct Writer {
+    writer: *mut crate::bindings::NTupleWriter,
+    validation: Option<ValidationOptions>,
+    output: Option<Box<dyn Write>>,
+}
+
+impl fmt::Debug for Writer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Writer")
+            .field("writer", &self.writer)
+            .field("validation", &self.validation)
+            .finish_non_exhaustive()
+    }
+}
 
 impl Writer {
     pub fn new<P: AsRef<Path>>(
@@ -23,41 +43,143 @@ impl Writer {
         name: &str,
     ) -> Result<Self, CreateError> {
         let filename = file.as_ref();
-        let file = match CString::new(filename.as_os_str().as_bytes()) {
-            Ok(f) => f,
-            Err(err) => panic!(
-                "Failed to create nTuple Writer to {filename:?}: Found nul byte at position {} in filename",
-                err.nul_position()
-
-            )
+        let Ok(file) = CString::new(filename.as_os_str().as_bytes()) else {
+            return Err(CreateError::NulByteInPath(filename.to_path_buf()));
         };
-        let name = match CString::new(name) {
-            Ok(f) => f,
-            Err(err) => {
-                panic!("Failed to create nTuple Writer with name {name}: {err}")
-            }
+        let Ok(name) = CString::new(name) else {
+            return Err(CreateError::NulByteInName(name.to_owned()));
         };
-        let NTupleWriterCreateResult { writer, error } = unsafe {
+        let res = unsafe {
             ntuple_create_writer(file.as_ptr(), name.as_ptr() as *const c_char)
         };
+        Self::from_create_result(res, filename.to_path_buf(), None)
+    }
+
+    /// Write a ROOT file to an arbitrary destination
+    ///
+    /// The file is kept in memory and only written to `output` in
+    /// [finish](Self::finish) or when the `Writer` is dropped. To
+    /// obtain the contents of the file, use [in_memory](Self::in_memory)
+    /// instead.
+    pub fn to_writer<W: Write + 'static>(
+        output: W,
+        name: &str,
+    ) -> Result<Self, CreateError> {
+        Self::create_memory(name, Some(Box::new(output)))
+    }
+
+    /// Write a ROOT file to memory
+    ///
+    /// The contents of the file are returned by
+    /// [into_bytes](Self::into_bytes).
+    pub fn in_memory(name: &str) -> Result<Self, CreateError> {
+        Self::create_memory(name, None)
+    }
+
+    fn create_memory(
+        name: &str,
+        output: Option<Box<dyn Write>>,
+    ) -> Result<Self, CreateError> {
+        let Ok(name) = CString::new(name) else {
+            return Err(CreateError::NulByteInName(name.to_owned()));
+        };
+        let res = unsafe { ntuple_create_memory_writer(name.as_ptr()) };
+        Self::from_create_result(res, "<memory>".into(), output)
+    }
+
+    fn from_create_result(
+        res: NTupleWriterCreateResult,
+        path: PathBuf,
+        output: Option<Box<dyn Write>>,
+    ) -> Result<Self, CreateError> {
+        let NTupleWriterCreateResult { writer, error } = res;
         if writer.is_null() {
             let err = match error {
-                NTupleCreateError::OPEN_FAILED => {
-                    CreateError::Create(filename.to_path_buf())
-                }
+                NTupleCreateError::OPEN_FAILED => CreateError::Create(path),
                 NTupleCreateError::NO_TTREE => CreateError::NoTTree,
-                NTupleCreateError::EXCEPTION => CreateError::Exception,
+                NTupleCreateError::EXCEPTION => {
+                    CreateError::Exception(last_error())
+                }
                 _ => CreateError::Unknown,
             };
             Err(err)
         } else {
             debug_assert_eq!(error, NTupleCreateError::NONE);
-            Ok(Self(writer))
+            Ok(Self {
+                writer,
+                validation: None,
+                output,
+            })
         }
     }
 
+    /// Write all remaining data and close the file
+    ///
+    /// Dropping the `Writer` has the same effect, but ignores errors.
+    pub fn finish(mut self) -> Result<(), WriteError> {
+        self.close().map(|_| ())
+    }
+
+    /// Write all remaining data and return the contents of the file
+    ///
+    /// Only writers created with [in_memory](Self::in_memory) return
+    /// any data. For all others, this is the same as
+    /// [finish](Self::finish) and the returned vector is empty.
+    pub fn into_bytes(mut self) -> Result<Vec<u8>, WriteError> {
+        self.close()
+    }
+
+    // Finish the file and return the contents of an in-memory file
+    // that are not written to `output`
+    fn close(&mut self) -> Result<Vec<u8>, WriteError> {
+        let size = unsafe { ntuple_finish_writer(self.writer) };
+        let res = if size < 0 {
+            Err(WriteError::Finish(last_error()))
+        } else {
+            let buf = if size > 0 {
+                let data = unsafe { ntuple_writer_buffer(self.writer) };
+                unsafe { slice::from_raw_parts(data.cast(), size as usize) }
+            } else {
+                &[]
+            };
+            match self.output.as_mut() {
+                Some(output) => output
+                    .write_all(buf)
+                    .and_then(|_| output.flush())
+                    .map(|_| Vec::new())
+                    .map_err(WriteError::Io),
+                None => Ok(buf.to_vec()),
+            }
+        };
+        unsafe { ntuple_delete_writer(self.writer) };
+        self.writer = ptr::null_mut();
+        res
+    }
+
+    /// Store run information in the output file
+    ///
+    /// Only fields that are set are written. Entries from earlier
+    /// calls are overwritten.
+    pub fn set_run_info(&mut self, info: &RunInfo) -> Result<(), RunInfoError> {
+        unsafe { run_info::write(self.writer, info) }
+    }
+
+    /// Enable or disable strict mode
+    ///
+    /// In strict mode, each event is validated with the given options
+    /// before writing it. Events with any issues are rejected.
+    pub fn set_strict(&mut self, validation: Option<ValidationOptions>) {
+        self.validation = validation;
+    }
+
     pub fn write(&mut self, event: &Event) -> Result<(), WriteError> {
         use WriteError::*;
+        if let Some(opts) = &self.validation {
+            let issues = event.validate(opts);
+            if !issues.is_empty() {
+                return Err(Invalid(issues));
+            }
+        }
         if event.nparticle < 0 {
             return Err(NegParticleNum(event.nparticle));
         }
@@ -127,7 +249,15 @@ impl Writer {
             part: event.part.into(),
             alphas_power: event.alphas_power,
         };
-        let res = unsafe { ntuple_write_event(self.0, &event) };
+        self.write_ntuple_event(&event)
+    }
+
+    // Write an entry as is, without any checks
+    pub(crate) fn write_ntuple_event(
+        &mut self,
+        event: &NTupleEvent,
+    ) -> Result<(), WriteError> {
+        let res = unsafe { ntuple_write_event(self.writer, event) };
         match res {
             NTupleWriteResult::WRITE_OK => Ok(()),
             err => Err(WriteError::from(err)),
@@ -135,7 +265,7 @@ impl Writer {
     }
 }
 
-#[derive(Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[derive(Debug, Error)]
 #[non_exhaustive]
 pub enum WriteError {
     #[error("Too many particles in event")]
@@ -148,11 +278,22 @@ pub enum WriteError {
     NegParticleNum(i32),
     #[error("Error filling event into TTree")]
     FillError,
+    #[error("Invalid event: {}", format_issues(.0))]
+    Invalid(Vec<ValidationIssue>),
+    #[error("Failed to finish writing: {0}")]
+    Finish(String),
+    #[error("Failed to write output")]
+    Io(#[source] io::Error),
 
     #[error("Unknown error")]
     UnknownError,
 }
 
+fn format_issues(issues: &[ValidationIssue]) -> String {
+    let issues = Vec::from_iter(issues.iter().map(|i| i.to_string()));
+    issues.join(", ")
+}
+
 impl From<NTupleWriteResult> for WriteError {
     fn from(r: NTupleWriteResult) -> Self {
         match r {
@@ -168,18 +309,31 @@ impl From<NTupleWriteResult> for WriteError {
 
 impl Drop for Writer {
     fn drop(&mut self) {
-        unsafe { ntuple_delete_writer(self.0) }
+        if self.writer.is_null() {
+            return;
+        }
+        if self.output.is_some() {
+            if let Err(err) = self.close() {
+                warn!("{err}");
+            }
+        } else {
+            unsafe { ntuple_delete_writer(self.writer) }
+        }
     }
 }
 
 #[derive(Clone, Debug, Error, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum CreateError {
+    #[error("Path {0:?} contains a nul byte")]
+    NulByteInPath(PathBuf),
+    #[error("Name {0:?} contains a nul byte")]
+    NulByteInName(String),
     #[error("Failed to create file {0:?}")]
     Create(PathBuf),
     #[error("Failed to create a `TTree` named \"BHSntuples\"")]
     NoTTree,
-    #[error("Encountered an exception during creation")]
-    Exception,
+    #[error("Encountered an exception during creation: {0}")]
+    Exception(String),
 
     #[error("Unknown error")]
     Unknown,
@@ -0,0 +1,212 @@
+//! Cross sections with statistical uncertainties
+//!
+//! The cross section is the sum of the event weights. Events with the
+//! same `id` stem from the same phase-space point and are correlated,
+//! so the uncertainty is estimated from the sums of weights over each
+//! group of consecutive events with the same `id`. With `N` groups
+//! and group weights `W_g`, the squared uncertainty is
+//! `Σ W_g² - (Σ W_g)² / N`.
+use std::{collections::HashSet, fmt};
+
+use crate::{event::Part, Event};
+
+/// Accumulator for cross sections, broken down by [Part]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CrossSection {
+    parts: [PartSums; Part::ALL.len()],
+}
+
+impl CrossSection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an event
+    ///
+    /// Events with the same `id` are expected to be consecutive, as
+    /// in ntuple files.
+    pub fn add(&mut self, event: &Event) {
+        self.parts[part_idx(event.part)].add(event.id, event.weight)
+    }
+
+    /// Result for the given event type
+    pub fn part(&self, part: Part) -> XSResult {
+        self.parts[part_idx(part)].result()
+    }
+
+    /// Combined result for all official event types
+    ///
+    /// This is the sum of the contributions from the event types
+    /// 'B', 'V', 'I', and 'R', i.e. the full NLO cross section.
+    /// Uncertainties are added in quadrature.
+    pub fn total(&self) -> XSResult {
+        let mut total = XSResult::default();
+        for part in Part::ALL.into_iter().filter(|p| p.is_official()) {
+            let res = self.part(part);
+            total.xs += res.xs;
+            total.err = total.err.hypot(res.err);
+            total.nevents += res.nevents;
+            total.nids += res.nids;
+        }
+        total
+    }
+}
+
+impl<'a> Extend<&'a Event> for CrossSection {
+    fn extend<T: IntoIterator<Item = &'a Event>>(&mut self, iter: T) {
+        for event in iter {
+            self.add(event)
+        }
+    }
+}
+
+impl Extend<Event> for CrossSection {
+    fn extend<T: IntoIterator<Item = Event>>(&mut self, iter: T) {
+        for event in iter {
+            self.add(&event)
+        }
+    }
+}
+
+/// Cross section with uncertainty
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct XSResult {
+    /// Sum of weights
+    pub xs: f64,
+    /// Statistical uncertainty
+    pub err: f64,
+    /// Number of events
+    pub nevents: usize,
+    /// Number of distinct event `id`s
+    ///
+    /// For the [total](CrossSection::total), this is the sum over the
+    /// event types, which stem from independent runs.
+    pub nids: usize,
+}
+
+impl fmt::Display for XSResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ± {}", self.xs, self.err)
+    }
+}
+
+fn part_idx(part: Part) -> usize {
+    Part::ALL.iter().position(|p| *p == part).unwrap()
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct PartSums {
+    sumw: f64,
+    // sum over squared weights of all completed groups
+    sumw2: f64,
+    nevents: usize,
+    // number of groups of consecutive events with the same id
+    ngroups: usize,
+    ids: HashSet<i32>,
+    // id and weight of the current group
+    current: Option<(i32, f64)>,
+}
+
+impl PartSums {
+    fn add(&mut self, id: i32, weight: f64) {
+        self.sumw += weight;
+        self.nevents += 1;
+        match &mut self.current {
+            Some((current_id, w)) if *current_id == id => *w += weight,
+            current => {
+                if let Some((_, w)) = current.replace((id, weight)) {
+                    self.sumw2 += w * w;
+                }
+                self.ngroups += 1;
+                self.ids.insert(id);
+            }
+        }
+    }
+
+    fn result(&self) -> XSResult {
+        let mut sumw2 = self.sumw2;
+        if let Some((_, w)) = self.current {
+            sumw2 += w * w;
+        }
+        let err = if self.ngroups > 0 {
+            let var = sumw2 - self.sumw * self.sumw / self.ngroups as f64;
+            var.max(0.).sqrt()
+        } else {
+            0.
+        };
+        XSResult {
+            xs: self.sumw,
+            err,
+            nevents: self.nevents,
+            nids: self.ids.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: i32, weight: f64, part: Part) -> Event {
+        Event {
+            id,
+            weight,
+            part,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn xsection() {
+        let events = [
+            event(0, 1., Part::B),
+            event(1, 3., Part::B),
+            event(0, 2., Part::R),
+            event(0, -1., Part::R),
+            event(1, 4., Part::R),
+            event(1, -2., Part::R),
+            event(0, 5., Part::S),
+        ];
+        let mut xs = CrossSection::new();
+        xs.extend(&events);
+
+        let b = xs.part(Part::B);
+        assert_eq!(b.xs, 4.);
+        assert_eq!(b.nevents, 2);
+        assert_eq!(b.nids, 2);
+        // 1 + 9 - 16 / 2
+        assert!((b.err - 2f64.sqrt()).abs() < 1e-12);
+
+        let r = xs.part(Part::R);
+        assert_eq!(r.xs, 3.);
+        assert_eq!(r.nevents, 4);
+        assert_eq!(r.nids, 2);
+        // 1 + 4 - 9 / 2
+        assert!((r.err - 0.5f64.sqrt()).abs() < 1e-12);
+
+        assert_eq!(xs.part(Part::V), XSResult::default());
+
+        let total = xs.total();
+        assert_eq!(total.xs, 7.);
+        assert_eq!(total.nevents, 6);
+        assert_eq!(total.nids, 4);
+        assert!((total.err - 2.5f64.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn repeated_id() {
+        let events = [
+            event(0, 1., Part::B),
+            event(1, 2., Part::B),
+            event(0, 3., Part::B),
+        ];
+        let mut xs = CrossSection::new();
+        xs.extend(&events);
+
+        let b = xs.part(Part::B);
+        assert_eq!(b.nevents, 3);
+        assert_eq!(b.nids, 2);
+        // three separate groups: 1 + 4 + 9 - 36 / 3
+        assert!((b.err - 2f64.sqrt()).abs() < 1e-12);
+    }
+}
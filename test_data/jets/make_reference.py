@@ -0,0 +1,92 @@
+#!/usr/bin/env python3
+"""Generate the reference jets used by the tests in src/jets.rs
+
+Writes `event.dat` with the momenta of a multi-jet parton-level event
+(one `px py pz E` line per parton) and `reference.dat` with the
+inclusive jets for the anti-kt, kt, and Cambridge/Aachen algorithms
+(one `algorithm R px py pz E` line per jet, ordered by decreasing pt).
+
+The jets are clustered with the FastJet python bindings
+(`pip install fastjet`). The first line of `reference.dat` records the
+FastJet version.
+"""
+
+import math
+import random
+from pathlib import Path
+
+import fastjet
+
+ALGORITHMS = ["antikt", "kt", "cambridge"]
+RADII = [0.4, 1.0]
+
+
+def generate_event(rng):
+    """Hard partons with collinear radiation and soft wide-angle partons"""
+    partons = []
+    for _ in range(4):
+        pt = rng.uniform(30.0, 200.0)
+        y = rng.uniform(-2.5, 2.5)
+        phi = rng.uniform(0.0, 2.0 * math.pi)
+        for _ in range(rng.randint(3, 8)):
+            z = rng.uniform(0.05, 0.6)
+            partons.append(
+                massless(
+                    z * pt,
+                    y + rng.gauss(0.0, 0.15),
+                    phi + rng.gauss(0.0, 0.15),
+                )
+            )
+            pt *= 1.0 - z
+    for _ in range(12):
+        partons.append(
+            massless(
+                rng.uniform(0.5, 5.0),
+                rng.uniform(-4.0, 4.0),
+                rng.uniform(0.0, 2.0 * math.pi),
+            )
+        )
+    # round to the precision written to the file
+    return [tuple(round(x, 6) for x in p) for p in partons]
+
+
+def massless(pt, y, phi):
+    return (
+        pt * math.cos(phi),
+        pt * math.sin(phi),
+        pt * math.sinh(y),
+        pt * math.cosh(y),
+    )
+
+
+def cluster_fastjet(partons, algorithm, radius):
+    algo = {
+        "antikt": fastjet.antikt_algorithm,
+        "kt": fastjet.kt_algorithm,
+        "cambridge": fastjet.cambridge_algorithm,
+    }[algorithm]
+    jet_def = fastjet.JetDefinition(algo, radius)
+    particles = [fastjet.PseudoJet(*p) for p in partons]
+    cs = fastjet.ClusterSequence(particles, jet_def)
+    jets = fastjet.sorted_by_pt(cs.inclusive_jets())
+    return [(j.px(), j.py(), j.pz(), j.E()) for j in jets]
+
+
+def main():
+    out_dir = Path(__file__).parent
+    partons = generate_event(random.Random(20240501))
+    with open(out_dir / "event.dat", "w") as out:
+        for p in partons:
+            out.write(" ".join(f"{x:.6f}" for x in p) + "\n")
+
+    with open(out_dir / "reference.dat", "w") as out:
+        out.write(f"# jets clustered with FastJet {fastjet.__version__}\n")
+        for algorithm in ALGORITHMS:
+            for radius in RADII:
+                for jet in cluster_fastjet(partons, algorithm, radius):
+                    momentum = " ".join(f"{x:.12e}" for x in jet)
+                    out.write(f"{algorithm} {radius} {momentum}\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,71 @@
+#!/usr/bin/env python3
+"""Create legacy.root from sample.root
+
+Legacy ntuple files have a `TTree` named `t3` instead of `BHSntuples`.
+This renames the tree in sample.root in place, without ROOT. To keep
+all offsets in the file unchanged, the title grows by as many
+characters as the name shrinks. The tree is stored as a single
+zlib-compressed block, which is recompressed into the space of the
+original one.
+"""
+
+import struct
+import zlib
+from pathlib import Path
+
+OLD_NAME = b"BHSntuples"
+NEW_NAME = b"t3"
+TITLE_SUFFIX = b", legacy"
+
+# ROOT compression block header: "ZL", method, 3-byte compressed and
+# 3-byte uncompressed size (little endian)
+BLOCK_HEADER_LEN = 9
+
+
+def named(name, title):
+    """Serialised name and title as in a `TKey` or `TNamed`"""
+    return bytes([len(name)]) + name + bytes([len(title)]) + title
+
+
+def rename(data, title):
+    old = named(OLD_NAME, title)
+    new = named(NEW_NAME, title + TITLE_SUFFIX)
+    assert len(old) == len(new)
+    assert data.count(old) >= 1
+    return data.replace(old, new)
+
+
+def main():
+    data_dir = Path(__file__).parent
+    data = bytearray((data_dir / "sample.root").read_bytes())
+
+    # find the key of the tree
+    class_name = b"\x05TTree"
+    pos = data.index(class_name + bytes([len(OLD_NAME)]) + OLD_NAME)
+    title_pos = pos + len(class_name) + 1 + len(OLD_NAME)
+    title = bytes(data[title_pos + 1 : title_pos + 1 + data[title_pos]])
+    # small file, so the key uses 32-bit seek pointers
+    key = pos - 26
+    nbytes, _, objlen, _, keylen = struct.unpack(">ihiih", data[key : key + 16])
+    assert struct.unpack(">i", data[key + 18 : key + 22])[0] == key
+
+    # rename the tree object
+    block = data[key + keylen : key + nbytes]
+    assert block[:2] == b"ZL"
+    tree = zlib.decompress(bytes(block[BLOCK_HEADER_LEN:]))
+    assert len(tree) == objlen
+    compressed = zlib.compress(rename(tree, title), 9)
+    assert BLOCK_HEADER_LEN + len(compressed) <= len(block)
+    header = block[:3] + len(compressed).to_bytes(3, "little")
+    header += objlen.to_bytes(3, "little")
+    block = header + compressed
+    block += bytes(nbytes - keylen - len(block))
+    data[key + keylen : key + nbytes] = block
+
+    # rename the key, both in front of the tree and in the list of keys
+    data = rename(bytes(data), title)
+    (data_dir / "legacy.root").write_bytes(data)
+
+
+if __name__ == "__main__":
+    main()